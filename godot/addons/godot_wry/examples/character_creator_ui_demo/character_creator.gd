@@ -54,7 +54,7 @@ func _input(event):
 		
 		$Character.rotate_y(rel.x * .5 * delta)
 
-func _on_web_view_ipc_message(message):
+func _on_web_view_ipc_message(message, _origin):
 	var data = JSON.parse_string(message)
 	
 	match data.type:
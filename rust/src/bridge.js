@@ -0,0 +1,278 @@
+(() => {
+  if (window.godot) return;
+
+  const pendingCalls = new Map();
+  const rpcHandlers = new Map();
+  const eventListeners = new Map();
+  let nextCallId = 0;
+  let binaryQueue = Promise.resolve();
+  let pressedTarget = null;
+  const stick = { x: 0, y: 0 };
+
+  const FOCUSABLE = "a[href], area[href], button, input, select, textarea, summary, iframe, [contenteditable], [tabindex]";
+  const DIRECTIONS = { ArrowUp: [0, -1], ArrowDown: [0, 1], ArrowLeft: [-1, 0], ArrowRight: [1, 0] };
+
+  // what a gamepad does once the page had a chance to handle its key: arrows move the focus
+  // to the closest element in their direction, Enter activates the focused element
+  const gamepadAction = (from, key) => {
+    const focused = from !== document.body && from !== document.documentElement;
+    if (key === "Enter") {
+      if (focused) from.click();
+      return;
+    }
+
+    const direction = DIRECTIONS[key];
+    if (!direction) return;
+
+    const center = (rect) => [rect.left + rect.width / 2, rect.top + rect.height / 2];
+    const [fromX, fromY] = focused ? center(from.getBoundingClientRect()) : [0, 0];
+    let best = null;
+    let bestScore = Infinity;
+    for (const element of document.querySelectorAll(FOCUSABLE)) {
+      if (element === from || element.disabled || element.tabIndex < 0) continue;
+      const rect = element.getBoundingClientRect();
+      if (rect.width === 0 || rect.height === 0) continue;
+
+      const [x, y] = center(rect);
+      const along = (x - fromX) * direction[0] + (y - fromY) * direction[1];
+      const across = Math.abs((x - fromX) * direction[1] - (y - fromY) * direction[0]);
+      // without a focused element, any direction starts from the top left
+      if (focused && along <= 0) continue;
+      const score = (focused ? along : Math.hypot(x, y)) + across * 2;
+      if (score < bestScore) {
+        best = element;
+        bestScore = score;
+      }
+    }
+
+    best?.focus();
+    best?.scrollIntoView({ block: "nearest", inline: "nearest" });
+  };
+
+  // tagged so Godot never mistakes them for user messages
+  const post = (kind, message = {}) => window.ipc.postMessage(JSON.stringify({ __godot_wry: kind, ...message }));
+
+  // custom protocols are exposed as http://<name>.localhost on Windows and Android
+  const ipcUrl = (path) =>
+    /Windows|Android/.test(navigator.userAgent)
+      ? `http://godot-ipc.localhost/${path}`
+      : `godot-ipc://localhost/${path}`;
+
+  window.godot = {
+    invoke(method, ...args) {
+      const id = nextCallId++;
+      return new Promise((resolve, reject) => {
+        pendingCalls.set(id, { resolve, reject });
+        post("rpc_call", { id, method, args });
+      });
+    },
+
+    postMessage(message) {
+      window.ipc.postMessage(JSON.stringify(message));
+    },
+
+    async postBinary(data) {
+      await fetch(ipcUrl("binary"), { method: "POST", body: data });
+    },
+
+    on(name, callback) {
+      if (!eventListeners.has(name)) eventListeners.set(name, new Set());
+      eventListeners.get(name).add(callback);
+      return () => this.off(name, callback);
+    },
+
+    off(name, callback) {
+      eventListeners.get(name)?.delete(callback);
+    },
+
+    emit(name, data) {
+      post("event", { name, data: data ?? null });
+    },
+
+    register(name, callback) {
+      rpcHandlers.set(name, callback);
+    },
+
+    unregister(name) {
+      rpcHandlers.delete(name);
+    },
+
+    // called by Godot to settle a promise returned by `invoke`
+    _resolve(id, result, error) {
+      const call = pendingCalls.get(id);
+      if (!call) return;
+      pendingCalls.delete(id);
+      if (error !== null) {
+        call.reject(new Error(error));
+      } else {
+        call.resolve(result);
+      }
+    },
+
+    // called by Godot to deliver an `emit_event`
+    _dispatch(name, data) {
+      for (const callback of eventListeners.get(name) ?? []) {
+        try {
+          callback(data);
+        } catch (e) {
+          console.error(`Error in "${name}" event listener`, e);
+        }
+      }
+    },
+
+    // called by Godot when a `post_binary` payload is ready to be fetched
+    _receiveBinary(id) {
+      // chained so payloads are dispatched in the order they were posted
+      binaryQueue = binaryQueue.then(async () => {
+        try {
+          const response = await fetch(ipcUrl(`binary/${id}`));
+          const data = new Uint8Array(await response.arrayBuffer());
+          document.dispatchEvent(new CustomEvent("binary_message", { detail: data }));
+        } catch (e) {
+          console.error("Failed to receive binary message from Godot", e);
+        }
+      });
+    },
+
+    // called by Godot to replay one of its input events in the page, see `dom_input_event` in input.rs
+    _input(event) {
+      const modifiers = {
+        ctrlKey: event.ctrlKey,
+        shiftKey: event.shiftKey,
+        altKey: event.altKey,
+        metaKey: event.metaKey,
+      };
+
+      if (event.kind === "joypad_motion") {
+        // the stick presses an arrow when pushed past halfway, and releases it when back
+        const direction = Math.abs(event.value) < 0.5 ? 0 : Math.sign(event.value);
+        const previous = stick[event.axis];
+        if (direction === previous) return;
+        stick[event.axis] = direction;
+
+        const arrows = event.axis === "x" ? { "-1": "ArrowLeft", 1: "ArrowRight" } : { "-1": "ArrowUp", 1: "ArrowDown" };
+        if (previous) this._input({ kind: "key_up", key: arrows[previous], code: arrows[previous], gamepad: true });
+        if (direction) this._input({ kind: "key_down", key: arrows[direction], code: arrows[direction], gamepad: true });
+        return;
+      }
+
+      if (event.kind === "key_down" || event.kind === "key_up") {
+        const target = document.activeElement ?? document.body;
+        const type = event.kind === "key_down" ? "keydown" : "keyup";
+        const init = { key: event.key, code: event.code, repeat: event.repeat, bubbles: true, cancelable: true, composed: true, ...modifiers };
+        const proceed = target.dispatchEvent(new KeyboardEvent(type, init));
+
+        // synthetic key events don't edit text, so do what the browser would have done
+        const editable = target.isContentEditable || target instanceof HTMLInputElement || target instanceof HTMLTextAreaElement;
+        if (proceed && type === "keydown" && editable && !event.ctrlKey && !event.metaKey) {
+          if (event.text) document.execCommand("insertText", false, event.text);
+          else if (event.key === "Backspace") document.execCommand("delete");
+          else if (event.key === "Delete") document.execCommand("forwardDelete");
+          else if (event.key === "Enter" && !(target instanceof HTMLInputElement)) document.execCommand("insertLineBreak");
+        }
+        if (proceed && type === "keydown" && event.gamepad) gamepadAction(target, event.key);
+        return;
+      }
+
+      // native webviews send window pixels, the inverse of what forward_input.js reports
+      const ratio = event.devicePixels ? window.devicePixelRatio : 1;
+      const x = event.x / ratio;
+      const y = event.y / ratio;
+      const target = document.elementFromPoint(x, y) ?? document.documentElement;
+      const init = {
+        clientX: x,
+        clientY: y,
+        button: event.button ?? 0,
+        buttons: event.buttons ?? 0,
+        bubbles: true,
+        cancelable: true,
+        composed: true,
+        view: window,
+        ...modifiers,
+      };
+      const pointer = { ...init, pointerId: 1, pointerType: "mouse", isPrimary: true };
+
+      switch (event.kind) {
+        case "mouse_move":
+          target.dispatchEvent(new PointerEvent("pointermove", pointer));
+          target.dispatchEvent(new MouseEvent("mousemove", init));
+          break;
+        case "mouse_down":
+          target.dispatchEvent(new PointerEvent("pointerdown", pointer));
+          if (target.dispatchEvent(new MouseEvent("mousedown", init))) target.focus?.();
+          pressedTarget = target;
+          break;
+        case "mouse_up": {
+          target.dispatchEvent(new PointerEvent("pointerup", pointer));
+          target.dispatchEvent(new MouseEvent("mouseup", init));
+          if (pressedTarget === target) {
+            if (init.button === 0) target.dispatchEvent(new MouseEvent("click", init));
+            if (init.button === 2) target.dispatchEvent(new MouseEvent("contextmenu", init));
+          }
+          pressedTarget = null;
+          break;
+        }
+        case "wheel": {
+          const wheel = { ...init, deltaX: event.deltaX, deltaY: event.deltaY, deltaMode: WheelEvent.DOM_DELTA_PIXEL };
+          if (!target.dispatchEvent(new WheelEvent("wheel", wheel))) break;
+          // scroll the closest element that can, like a real wheel would
+          let scrollable = target;
+          while (scrollable && scrollable !== document.documentElement) {
+            const style = getComputedStyle(scrollable);
+            const canScrollY = /(auto|scroll)/.test(style.overflowY) && scrollable.scrollHeight > scrollable.clientHeight;
+            const canScrollX = /(auto|scroll)/.test(style.overflowX) && scrollable.scrollWidth > scrollable.clientWidth;
+            if ((event.deltaY && canScrollY) || (event.deltaX && canScrollX)) break;
+            scrollable = scrollable.parentElement;
+          }
+          (scrollable && scrollable !== document.documentElement ? scrollable : document.scrollingElement)?.scrollBy(event.deltaX, event.deltaY);
+          break;
+        }
+      }
+    },
+
+    // called by Godot to run a function registered with `register`
+    async _call(id, method, args) {
+      try {
+        const handler = rpcHandlers.get(method);
+        if (!handler) throw new Error(`Unknown RPC method "${method}"`);
+        const result = await handler(...args);
+        post("rpc_result", { id, result: result ?? null, error: null });
+      } catch (e) {
+        post("rpc_result", { id, result: null, error: String(e?.message ?? e) });
+      }
+    },
+  };
+
+  // let Godot know when the URL changes without a page load, such as with `history.pushState`
+  if (window === window.top) {
+    const notifyUrlChanged = () => {
+      // the Navigation API isn't available everywhere. Without it, Linux asks WebKitGTK and other platforms report no history
+      const navigation = window.navigation;
+      post("url_changed", {
+        canGoBack: navigation?.canGoBack ?? null,
+        canGoForward: navigation?.canGoForward ?? null,
+      });
+    };
+    for (const name of ["pushState", "replaceState"]) {
+      const original = history[name];
+      history[name] = function (...args) {
+        const result = original.apply(this, args);
+        notifyUrlChanged();
+        return result;
+      };
+    }
+    window.addEventListener("popstate", notifyUrlChanged);
+    window.addEventListener("hashchange", notifyUrlChanged);
+    notifyUrlChanged();
+  }
+
+  // let Godot know the page's listeners are in place, so it can flush queued messages
+  if (window === window.top) {
+    const notifyReady = () => post("bridge_ready");
+    if (document.readyState === "loading") {
+      document.addEventListener("DOMContentLoaded", notifyReady, { once: true });
+    } else {
+      notifyReady();
+    }
+  }
+})();
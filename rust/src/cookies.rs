@@ -0,0 +1,218 @@
+use godot::prelude::*;
+use wry::cookie::Cookie;
+
+pub fn cookie_to_dictionary(cookie: &Cookie) -> Dictionary {
+    let mut dictionary = Dictionary::new();
+    dictionary.set("name", cookie.name());
+    dictionary.set("value", cookie.value());
+    dictionary.set("domain", cookie.domain().unwrap_or_default());
+    dictionary.set("path", cookie.path().unwrap_or_default());
+    dictionary.set("secure", cookie.secure().unwrap_or(false));
+    dictionary.set("http_only", cookie.http_only().unwrap_or(false));
+    if let Some(expires) = cookie.expires_datetime() {
+        dictionary.set("expires", expires.unix_timestamp());
+    }
+    if let Some(same_site) = cookie.same_site() {
+        dictionary.set("same_site", same_site.to_string());
+    }
+    dictionary
+}
+
+/// Cookie fields read from the dictionary given to `set_cookie`.
+pub struct CookieParams {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    pub expires: Option<i64>,
+    pub same_site: Option<String>,
+}
+
+impl CookieParams {
+    pub fn from_dictionary(dictionary: &Dictionary) -> Result<Self, String> {
+        let string = |key: &str| dictionary.get(key).map(|v| v.stringify().to_string()).filter(|s| !s.is_empty());
+        let flag = |key: &str| dictionary.get(key).and_then(|v| v.try_to::<bool>().ok()).unwrap_or(false);
+
+        // the domain can be omitted in favor of the url the cookie belongs to
+        let domain = string("domain").or_else(|| {
+            string("url")
+                .and_then(|url| url.parse::<http::Uri>().ok())
+                .and_then(|uri| uri.host().map(String::from))
+        });
+
+        let params = Self {
+            name: string("name").ok_or("a cookie needs at least a name")?,
+            value: string("value").unwrap_or_default(),
+            domain: domain.unwrap_or_default(),
+            path: string("path").unwrap_or_else(|| "/".into()),
+            secure: flag("secure"),
+            http_only: flag("http_only"),
+            expires: dictionary.get("expires").and_then(|v| v.try_to::<i64>().ok()),
+            same_site: string("same_site"),
+        };
+
+        // these would end the field early and let it add attributes of its own, such as `; domain=`
+        let fields = [("name", &params.name), ("value", &params.value), ("domain", &params.domain), ("path", &params.path)];
+        for (field, value) in fields {
+            if let Some(c) = value.chars().find(|c| COOKIE_DELIMITERS.contains(c)) {
+                return Err(format!("the cookie's {} can't contain {:?}", field, c));
+            }
+        }
+        if params.name.contains('=') {
+            return Err("the cookie's name can't contain '='".into());
+        }
+
+        Ok(params)
+    }
+
+    /// `document.cookie` assignment, used where no native cookie manager is available.
+    /// It only reaches the current page's domain and can't set `HttpOnly` cookies.
+    #[cfg(not(target_os = "linux"))]
+    fn to_document_cookie(&self) -> String {
+        let mut cookie = format!("{}={}; path={}", self.name, self.value, self.path);
+        if !self.domain.is_empty() {
+            cookie.push_str(&format!("; domain={}", self.domain));
+        }
+        if let Some(expires) = self.expires {
+            cookie.push_str(&format!("; max-age={}", expires - unix_now()));
+        }
+        if self.secure {
+            cookie.push_str("; secure");
+        }
+        if let Some(same_site) = &self.same_site {
+            cookie.push_str(&format!("; samesite={}", same_site));
+        }
+        cookie
+    }
+}
+
+/// Characters that separate cookies and their attributes.
+const COOKIE_DELIMITERS: &[char] = &[';', ',', '\r', '\n'];
+
+#[cfg(not(target_os = "linux"))]
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use super::CookieParams;
+    use gtk::gio::Cancellable;
+    use gtk::glib;
+    use webkit2gtk::prelude::*;
+    use webkit2gtk::WebsiteDataTypes;
+    use wry::WebViewExtUnix;
+
+    fn cookie_manager(webview: &wry::WebView) -> Option<webkit2gtk::CookieManager> {
+        webview.webview().context().and_then(|context| context.cookie_manager())
+    }
+
+    fn soup_cookie(name: &str, value: &str, domain: &str, path: &str) -> soup::Cookie {
+        soup::Cookie::new(name, value, domain, path, -1)
+    }
+
+    pub fn set_cookie(webview: &wry::WebView, params: &CookieParams) -> Result<(), String> {
+        // the cookie manager silently drops cookies without a domain
+        if params.domain.is_empty() {
+            return Err("a cookie needs a domain or a url".into());
+        }
+        let manager = cookie_manager(webview).ok_or("the webview has no cookie manager")?;
+
+        let mut cookie = soup_cookie(&params.name, &params.value, &params.domain, &params.path);
+        cookie.set_secure(params.secure);
+        cookie.set_http_only(params.http_only);
+        if let Some(expires) = params.expires.and_then(|e| glib::DateTime::from_unix_utc(e).ok()) {
+            cookie.set_expires(&expires);
+        }
+        match params.same_site.as_deref().map(str::to_lowercase).as_deref() {
+            Some("strict") => cookie.set_same_site_policy(soup::SameSitePolicy::Strict),
+            Some("lax") => cookie.set_same_site_policy(soup::SameSitePolicy::Lax),
+            Some("none") => cookie.set_same_site_policy(soup::SameSitePolicy::None),
+            _ => {}
+        }
+
+        manager.add_cookie(&mut cookie, None::<&Cancellable>, |_| {});
+        Ok(())
+    }
+
+    pub fn delete_cookie(webview: &wry::WebView, name: &str, url: &str) {
+        let Some(manager) = cookie_manager(webview) else { return };
+
+        let cookies = webview.cookies_for_url(url).unwrap_or_default();
+        for cookie in cookies.iter().filter(|cookie| cookie.name() == name) {
+            let mut cookie = soup_cookie(
+                cookie.name(),
+                cookie.value(),
+                cookie.domain().unwrap_or_default(),
+                cookie.path().unwrap_or("/"),
+            );
+            manager.delete_cookie(&mut cookie, None::<&Cancellable>, |_| {});
+        }
+    }
+
+    pub fn clear_cookies(webview: &wry::WebView) {
+        if let Some(data_manager) = webview.webview().website_data_manager() {
+            data_manager.clear(WebsiteDataTypes::COOKIES, glib::TimeSpan::from_seconds(0), None::<&Cancellable>, |_| {});
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use native::{clear_cookies, delete_cookie, set_cookie};
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_cookie(webview: &wry::WebView, params: &CookieParams) -> Result<(), String> {
+    // scripts can't set `HttpOnly` cookies, it would silently become one the page can read
+    if params.http_only {
+        return Err("HttpOnly cookies can't be set on this platform".into());
+    }
+
+    // `document.cookie` only reaches the current page's domain and the domains it belongs to
+    let host = webview
+        .url()
+        .ok()
+        .and_then(|url| url.parse::<http::Uri>().ok())
+        .and_then(|uri| uri.host().map(String::from))
+        .ok_or("no page is loaded, cookies can only be set for the current page on this platform")?;
+    let domain = params.domain.trim_start_matches('.');
+    if !domain.is_empty() && !host.eq_ignore_ascii_case(domain) && !host.to_lowercase().ends_with(&format!(".{}", domain.to_lowercase())) {
+        return Err(format!(
+            "the cookie's domain {} isn't the current page's ({}), cookies can only be set for the current page on this platform",
+            params.domain, host
+        ));
+    }
+
+    let script = format!("document.cookie = {}", serde_json::Value::String(params.to_document_cookie()));
+    webview.evaluate_script(&script).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn expire_cookie(webview: &wry::WebView, cookie: &Cookie) {
+    let expired = format!(
+        "{}=; path={}; domain={}; max-age=0",
+        cookie.name(),
+        cookie.path().unwrap_or("/"),
+        cookie.domain().unwrap_or_default()
+    );
+    let _ = webview.evaluate_script(&format!("document.cookie = {}", serde_json::Value::String(expired)));
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn delete_cookie(webview: &wry::WebView, name: &str, url: &str) {
+    let cookies = webview.cookies_for_url(url).unwrap_or_default();
+    for cookie in cookies.iter().filter(|cookie| cookie.name() == name) {
+        expire_cookie(webview, cookie);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn clear_cookies(webview: &wry::WebView) {
+    for cookie in webview.cookies().unwrap_or_default() {
+        expire_cookie(webview, &cookie);
+    }
+}
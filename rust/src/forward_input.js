@@ -0,0 +1,152 @@
+(() => {
+  // Everything this script relies on is captured before any page script runs,
+  // so pages can neither read the token nor tamper with the events we send.
+  const token = "__GODOT_WRY_INPUT_TOKEN__";
+  const apply = Reflect.apply;
+  const stringify = JSON.stringify;
+  const getOwnPropertyDescriptor = Object.getOwnPropertyDescriptor;
+  const hasFocus = Document.prototype.hasFocus;
+
+  const channel = window.webkit?.messageHandlers?.ipc ?? window.chrome?.webview ?? window.ipc;
+  const channelPostMessage = channel.postMessage;
+  const postMessage = (message) => apply(channelPostMessage, channel, [message]);
+
+  const getter = (proto, name) => {
+    const get = getOwnPropertyDescriptor(proto, name).get;
+    return (target) => apply(get, target, []);
+  };
+  const devicePixelRatio = getter(window, "devicePixelRatio");
+  const clientX = getter(MouseEvent.prototype, "clientX");
+  const clientY = getter(MouseEvent.prototype, "clientY");
+  const movementX = getter(MouseEvent.prototype, "movementX");
+  const movementY = getter(MouseEvent.prototype, "movementY");
+  const button = getter(MouseEvent.prototype, "button");
+  const deltaX = getter(WheelEvent.prototype, "deltaX");
+  const deltaY = getter(WheelEvent.prototype, "deltaY");
+  const deltaMode = getter(WheelEvent.prototype, "deltaMode");
+  // non-standard, but Chromium and WebKit both have it
+  const wheelDelta = (name) =>
+    getOwnPropertyDescriptor(WheelEvent.prototype, name) ? getter(WheelEvent.prototype, name) : () => 0;
+  const wheelDeltaX = wheelDelta("wheelDeltaX");
+  const wheelDeltaY = wheelDelta("wheelDeltaY");
+  const pointerId = getter(PointerEvent.prototype, "pointerId");
+  const pointerType = getter(PointerEvent.prototype, "pointerType");
+  const pressure = getter(PointerEvent.prototype, "pressure");
+  const tiltX = getter(PointerEvent.prototype, "tiltX");
+  const tiltY = getter(PointerEvent.prototype, "tiltY");
+  const buttons = getter(MouseEvent.prototype, "buttons");
+  const key = getter(KeyboardEvent.prototype, "key");
+  const code = getter(KeyboardEvent.prototype, "code");
+  const keyCode = getter(KeyboardEvent.prototype, "keyCode");
+  const repeat = getter(KeyboardEvent.prototype, "repeat");
+  const shiftKey = getter(KeyboardEvent.prototype, "shiftKey");
+  const ctrlKey = getter(KeyboardEvent.prototype, "ctrlKey");
+  const altKey = getter(KeyboardEvent.prototype, "altKey");
+  const metaKey = getter(KeyboardEvent.prototype, "metaKey");
+  const keyFields = (e) => [
+    "key", key(e),
+    "code", code(e),
+    "keyCode", keyCode(e),
+    "repeat", repeat(e),
+    "shiftKey", shiftKey(e),
+    "ctrlKey", ctrlKey(e),
+    "altKey", altKey(e),
+    "metaKey", metaKey(e),
+  ];
+
+  // the message is assembled by hand, stringifying an object would call a page-defined `toJSON`
+  const send = (type, fields) => {
+    let event = '{"type":' + stringify(type);
+    for (let i = 0; i < fields.length; i += 2) {
+      event += "," + stringify(fields[i]) + ":" + stringify(fields[i + 1]);
+    }
+    postMessage('{"__godot_wry":"input","token":"' + token + '","event":' + event + "}}");
+  };
+
+  // synthetic events dispatched by page scripts are never forwarded
+  const listen = (type, callback) => {
+    document.addEventListener(type, (e) => {
+      if (!e.isTrusted || !apply(hasFocus, document, [])) return;
+      callback(e);
+    });
+  };
+
+  // touch and pen also fire compatibility mouse events, they are forwarded from pointer events instead.
+  // Pointer events come first, so the last pointer type tells where a mouse event came from.
+  let lastPointerType = "mouse";
+  const fromPointer = () => lastPointerType !== "mouse";
+
+  const TOUCH_TYPES = { pointerdown: "touch_down", pointermove: "touch_move", pointerup: "touch_up", pointercancel: "touch_cancel" };
+  const PEN_TYPES = { pointerdown: "mouse_down", pointermove: "mouse_move", pointerup: "mouse_up", pointercancel: "mouse_up" };
+  for (const type of ["pointerdown", "pointermove", "pointerup", "pointercancel"]) {
+    listen(type, (e) => {
+      lastPointerType = pointerType(e);
+      if (lastPointerType === "mouse") return;
+
+      const ratio = devicePixelRatio(window);
+      if (lastPointerType === "touch") {
+        send(TOUCH_TYPES[type], ["id", pointerId(e), "x", clientX(e) * ratio, "y", clientY(e) * ratio]);
+        return;
+      }
+
+      // pens are mice to Godot, with pressure and tilt. An eraser is button 5 in the DOM
+      const eraser = button(e) === 5 || (buttons(e) & 32) !== 0;
+      send(PEN_TYPES[type], [
+        "x", clientX(e) * ratio,
+        "y", clientY(e) * ratio,
+        "movementX", movementX(e) * ratio,
+        "movementY", movementY(e) * ratio,
+        "button", button(e) === 5 ? 0 : button(e),
+        "pressure", pressure(e),
+        "tiltX", tiltX(e),
+        "tiltY", tiltY(e),
+        "eraser", eraser,
+      ]);
+    });
+  }
+
+  listen("mousemove", (e) => {
+    if (fromPointer()) return;
+    const ratio = devicePixelRatio(window);
+    send("mouse_move", [
+      "x", clientX(e) * ratio,
+      "y", clientY(e) * ratio,
+      "movementX", movementX(e) * ratio,
+      "movementY", movementY(e) * ratio,
+      "button", button(e),
+    ]);
+  });
+  listen("mousedown", (e) => {
+    if (fromPointer()) return;
+    const ratio = devicePixelRatio(window);
+    send("mouse_down", ["x", clientX(e) * ratio, "y", clientY(e) * ratio, "button", button(e)]);
+  });
+  listen("mouseup", (e) => {
+    if (fromPointer()) return;
+    const ratio = devicePixelRatio(window);
+    send("mouse_up", ["x", clientX(e) * ratio, "y", clientY(e) * ratio, "button", button(e)]);
+  });
+  listen("wheel", (e) => {
+    const ratio = devicePixelRatio(window);
+    // engines count wheel notches as 120 wheelDelta units (WebKit derives deltaY from the same count,
+    // so comparing the two can't tell them apart), smooth scrolling devices report fractions of a notch
+    const [notchesX, notchesY] = [wheelDeltaX(e) / 120, wheelDeltaY(e) / 120];
+    const smooth = !Number.isInteger(notchesX) || !Number.isInteger(notchesY);
+    const trackpad = deltaMode(e) === 0 && smooth;
+    // deltas stay in CSS pixels, Godot's wheel factor counts notches whatever the screen's scale
+    send("wheel", [
+      "x", clientX(e) * ratio,
+      "y", clientY(e) * ratio,
+      "deltaX", deltaX(e),
+      "deltaY", deltaY(e),
+      "deltaMode", deltaMode(e),
+      "trackpad", trackpad,
+    ]);
+  });
+  listen("keydown", (e) => {
+    send("key_down", keyFields(e));
+  });
+  listen("keyup", (e) => {
+    send("key_up", keyFields(e));
+  });
+})();
@@ -0,0 +1,783 @@
+use godot::classes::{
+    Input, InputEvent, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey, InputEventMouseButton,
+    InputEventMouseMotion, InputEventPanGesture, InputEventScreenDrag, InputEventScreenTouch, InputEventWithModifiers,
+};
+use godot::global::{JoyAxis, JoyButton, Key, MouseButton, MouseButtonMask};
+use godot::prelude::*;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Forwarded input events waiting to be handed over to Godot.
+#[derive(Default)]
+pub struct InputQueue {
+    events: Vec<Gd<InputEvent>>,
+}
+
+impl InputQueue {
+    /// Queues `event`. With `coalesce_motion`, a mouse motion following another one is merged into it,
+    /// keeping the latest position, buttons and pen state and summing `relative`,
+    /// so button and key events keep their order.
+    pub fn push(&mut self, event: Gd<InputEvent>, coalesce_motion: bool) {
+        if coalesce_motion {
+            if let Ok(motion) = event.clone().try_cast::<InputEventMouseMotion>() {
+                let last_motion = self.events.last().and_then(|last| last.clone().try_cast::<InputEventMouseMotion>().ok());
+                if let Some(mut last_motion) = last_motion {
+                    let relative = last_motion.get_relative() + motion.get_relative();
+                    last_motion.set_position(motion.get_position());
+                    last_motion.set_global_position(motion.get_global_position());
+                    last_motion.set_relative(relative);
+                    last_motion.set_button_mask(motion.get_button_mask());
+                    last_motion.set_pressure(motion.get_pressure());
+                    last_motion.set_tilt(motion.get_tilt());
+                    last_motion.set_pen_inverted(motion.get_pen_inverted());
+                    return;
+                }
+            }
+        }
+
+        self.events.push(event);
+    }
+
+    pub fn flush(&mut self) {
+        let mut input = Input::singleton();
+        for event in self.events.drain(..) {
+            input.parse_input_event(&event);
+        }
+    }
+}
+
+/// The mouse buttons and touches currently down in a webview's page, as forwarded to Godot.
+#[derive(Default)]
+pub struct PointerState {
+    button_mask: MouseButtonMask,
+    /// Touches by DOM pointer id, with their Godot index and last position.
+    touches: HashMap<i64, (i32, Vector2)>,
+}
+
+/// Builds the Godot input events for a DOM event sent by the input forwarding script.
+/// DOM coordinates are relative to the webview, `webview_origin` moves them into the window.
+pub fn forwarded_input_events(event: &Value, webview_origin: Vector2, pointer: &mut PointerState) -> Vec<Gd<InputEvent>> {
+    let Some(event_type) = event.get("type").and_then(|t| t.as_str()) else { return Vec::new() };
+
+    match event_type {
+        "mouse_move" => {
+            let x = event.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let y = event.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+
+            let movement_x = event.get("movementX").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let movement_y = event.get("movementY").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+
+            let position = webview_origin + Vector2::new(x, y);
+            let mut motion = InputEventMouseMotion::new_gd();
+            motion.set_position(position);
+            motion.set_global_position(position);
+
+            motion.set_button_mask(pointer.button_mask);
+
+            motion.set_relative(Vector2::new(movement_x, movement_y));
+
+            // only pens report pressure and tilt, tilt is in degrees in the DOM and -1..1 in Godot
+            if let Some(pressure) = event.get("pressure").and_then(|v| v.as_f64()) {
+                let tilt_x = event.get("tiltX").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+                let tilt_y = event.get("tiltY").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+                motion.set_pressure(pressure as f32);
+                motion.set_tilt(Vector2::new(tilt_x, tilt_y) / 90.0);
+                motion.set_pen_inverted(event.get("eraser").and_then(|v| v.as_bool()).unwrap_or(false));
+            }
+
+            vec![motion.upcast()]
+        },
+
+        "mouse_down" | "mouse_up" => {
+            let x = event.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let y = event.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let button = event.get("button").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+
+            // DOM buttons are numbered left, middle, right, back, forward
+            let (godot_button, mask) = match button {
+                0 => (MouseButton::LEFT, MouseButtonMask::LEFT),
+                1 => (MouseButton::MIDDLE, MouseButtonMask::MIDDLE),
+                2 => (MouseButton::RIGHT, MouseButtonMask::RIGHT),
+                3 => (MouseButton::XBUTTON1, MouseButtonMask::MB_XBUTTON1),
+                4 => (MouseButton::XBUTTON2, MouseButtonMask::MB_XBUTTON2),
+                _ => return Vec::new(),
+            };
+
+            let pressed = event_type == "mouse_down";
+            pointer.button_mask = if pressed {
+                pointer.button_mask | mask
+            } else {
+                MouseButtonMask::from_ord(pointer.button_mask.ord() & !mask.ord())
+            };
+
+            let mut mouse_button = InputEventMouseButton::new_gd();
+            mouse_button.set_button_index(godot_button);
+            let position = webview_origin + Vector2::new(x, y);
+            mouse_button.set_position(position);
+            mouse_button.set_global_position(position);
+            mouse_button.set_pressed(pressed);
+            mouse_button.set_button_mask(pointer.button_mask);
+
+            vec![mouse_button.upcast()]
+        },
+
+        "touch_down" | "touch_up" | "touch_cancel" | "touch_move" => {
+            let x = event.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let y = event.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let position = webview_origin + Vector2::new(x, y);
+            let Some(pointer_id) = event.get("id").and_then(|v| v.as_i64()) else { return Vec::new() };
+
+            let touches = &mut pointer.touches;
+
+            if event_type == "touch_move" {
+                // a pointer that went down outside of the webview has no index
+                let Some((index, previous)) = touches.get_mut(&pointer_id) else { return Vec::new() };
+                let mut drag = InputEventScreenDrag::new_gd();
+                drag.set_index(*index);
+                drag.set_position(position);
+                drag.set_relative(position - *previous);
+                *previous = position;
+                return vec![drag.upcast()];
+            }
+
+            let pressed = event_type == "touch_down";
+            let index = if pressed {
+                // like Godot, a new touch takes the lowest index not in use
+                let index = (0..).find(|i| touches.values().all(|(used, _)| used != i)).unwrap_or_default();
+                touches.insert(pointer_id, (index, position));
+                index
+            } else {
+                let Some((index, _)) = touches.remove(&pointer_id) else { return Vec::new() };
+                index
+            };
+
+            let mut touch = InputEventScreenTouch::new_gd();
+            touch.set_index(index);
+            touch.set_position(position);
+            touch.set_pressed(pressed);
+            touch.set_canceled(event_type == "touch_cancel");
+
+            vec![touch.upcast()]
+        },
+
+        "wheel" => {
+            let x = event.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let y = event.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
+            let position = webview_origin + Vector2::new(x, y);
+
+            // deltas in pixels, lines and pages are converted to how far a browser would scroll
+            let scale = match event.get("deltaMode").and_then(|v| v.as_i64()).unwrap_or(0) {
+                1 => WHEEL_LINE,
+                2 => WHEEL_STEP * 10.0,
+                _ => 1.0,
+            };
+            let delta_x = event.get("deltaX").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32 * scale;
+            let delta_y = event.get("deltaY").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32 * scale;
+            let button_mask = pointer.button_mask;
+
+            // trackpads scroll smoothly, Godot reports that as a pan gesture, in wheel notches
+            if event.get("trackpad").and_then(|v| v.as_bool()).unwrap_or(false) {
+                let mut pan = InputEventPanGesture::new_gd();
+                pan.set_position(position);
+                pan.set_delta(Vector2::new(delta_x, delta_y) / WHEEL_STEP);
+                return vec![pan.upcast()];
+            }
+
+            let axes = [
+                (delta_y, MouseButton::WHEEL_UP, MouseButton::WHEEL_DOWN),
+                (delta_x, MouseButton::WHEEL_LEFT, MouseButton::WHEEL_RIGHT),
+            ];
+
+            // like Godot's own wheel events, each scroll is a press immediately followed by a release
+            let mut events = Vec::new();
+            for (delta, negative, positive) in axes.into_iter().filter(|(delta, _, _)| *delta != 0.0) {
+                for pressed in [true, false] {
+                    let mut wheel = InputEventMouseButton::new_gd();
+                    wheel.set_button_index(if delta < 0.0 { negative } else { positive });
+                    wheel.set_factor(delta.abs() / WHEEL_STEP);
+                    wheel.set_position(position);
+                    wheel.set_global_position(position);
+                    wheel.set_button_mask(button_mask);
+                    wheel.set_pressed(pressed);
+                    events.push(wheel.upcast());
+                }
+            }
+            events
+        },
+
+        "key_down" | "key_up" => {
+            let forwarded = forwarded_key(event);
+
+            let mut key = InputEventKey::new_gd();
+            key.set_keycode(forwarded.keycode);
+            key.set_physical_keycode(forwarded.physical_keycode);
+            // `key_label` only exists since Godot 4.2
+            key.set("key_label", &forwarded.keycode.to_variant());
+            if let Some(text) = forwarded.unicode {
+                key.set_unicode(text as _);
+            }
+
+            key.set_shift_pressed(forwarded.shift);
+            key.set_ctrl_pressed(forwarded.ctrl);
+            key.set_alt_pressed(forwarded.alt);
+            key.set_meta_pressed(forwarded.meta);
+            key.set_echo(forwarded.echo);
+            key.set_pressed(forwarded.pressed);
+
+            vec![key.upcast()]
+        },
+
+        _ => Vec::new(),
+    }
+}
+
+/// What a forwarded `KeyboardEvent` becomes in Godot, before it's made into an `InputEventKey`.
+#[derive(Debug, PartialEq)]
+struct ForwardedKey {
+    keycode: Key,
+    physical_keycode: Key,
+    unicode: Option<char>,
+    shift: bool,
+    ctrl: bool,
+    alt: bool,
+    meta: bool,
+    echo: bool,
+    pressed: bool,
+}
+
+fn forwarded_key(event: &Value) -> ForwardedKey {
+    let key_str = event.get("key").and_then(|v| v.as_str()).unwrap_or("");
+    let code = event.get("code").and_then(|v| v.as_str()).unwrap_or("");
+    let flag = |name: &str| event.get(name).and_then(|v| v.as_bool()).unwrap_or(false);
+    let shift = flag("shiftKey");
+
+    // `code` is the key's position, whatever the layout and modifiers
+    let physical_key = physical_key(code);
+
+    // Godot's keycode is the unshifted key, Shift+1 is KEY_1 and not EXCLAM
+    let text = single_char(key_str);
+    let godot_key = match GODOT_KEYS.get(key_str).copied() {
+        Some(_) if shift && text.is_some_and(|c| !c.is_alphabetic()) && physical_key != Key::NONE => physical_key,
+        Some(godot_key) => godot_key,
+        None => physical_key,
+    };
+
+    ForwardedKey {
+        keycode: godot_key,
+        physical_keycode: physical_key,
+        unicode: text,
+        shift,
+        ctrl: flag("ctrlKey"),
+        alt: flag("altKey"),
+        meta: flag("metaKey"),
+        echo: flag("repeat"),
+        pressed: event.get("type").and_then(|t| t.as_str()) == Some("key_down"),
+    }
+}
+
+/// The character a key types, named keys such as `Enter` have none.
+fn single_char(key: &str) -> Option<char> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() => Some(c),
+        _ => None,
+    }
+}
+
+/// The Godot key at the position a `KeyboardEvent.code` describes.
+fn physical_key(code: &str) -> Key {
+    if let Some(letter) = code.strip_prefix("Key").and_then(single_char) {
+        return Key::from_ord(letter as i32);
+    }
+    if let Some(digit) = code.strip_prefix("Digit").and_then(single_char) {
+        return Key::from_ord(digit as i32);
+    }
+
+    match code {
+        "ShiftLeft" | "ShiftRight" => Key::SHIFT,
+        "ControlLeft" | "ControlRight" => Key::CTRL,
+        "AltLeft" | "AltRight" => Key::ALT,
+        "MetaLeft" | "MetaRight" | "OSLeft" | "OSRight" => Key::META,
+        _ => NAMED_DOM_KEYS
+            .iter()
+            .find(|(_, _, dom_code)| *dom_code == code)
+            .map(|(key, _, _)| *key)
+            .or_else(|| GODOT_KEYS.get(code).copied())
+            .unwrap_or(Key::NONE),
+    }
+}
+
+/// Describes a Godot input event as the DOM event `window.godot._input` dispatches in the page.
+/// `position` is where the event happens, in CSS pixels from the page's top left corner.
+pub fn dom_input_event(event: &Gd<InputEvent>, position: Vector2) -> Option<Value> {
+    let mut dom_event = if let Ok(motion) = event.clone().try_cast::<InputEventMouseMotion>() {
+        json!({ "kind": "mouse_move", "buttons": motion.get_button_mask().ord() })
+    } else if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+        let index = button.get_button_index();
+        let wheel_delta = match index {
+            MouseButton::WHEEL_UP => Some((0.0, -1.0)),
+            MouseButton::WHEEL_DOWN => Some((0.0, 1.0)),
+            MouseButton::WHEEL_LEFT => Some((-1.0, 0.0)),
+            MouseButton::WHEEL_RIGHT => Some((1.0, 0.0)),
+            _ => None,
+        };
+
+        match wheel_delta {
+            // Godot reports a wheel notch as a press and a release, only the press scrolls
+            Some(_) if !button.is_pressed() => return None,
+            Some((x, y)) => {
+                let factor = if button.get_factor() > 0.0 { button.get_factor() } else { 1.0 };
+                json!({ "kind": "wheel", "deltaX": x * factor * WHEEL_STEP, "deltaY": y * factor * WHEEL_STEP })
+            },
+            None => {
+                // DOM buttons are numbered left, middle, right, back, forward
+                let dom_button = match index {
+                    MouseButton::LEFT => 0,
+                    MouseButton::MIDDLE => 1,
+                    MouseButton::RIGHT => 2,
+                    MouseButton::XBUTTON1 => 3,
+                    MouseButton::XBUTTON2 => 4,
+                    _ => return None,
+                };
+                json!({
+                    "kind": if button.is_pressed() { "mouse_down" } else { "mouse_up" },
+                    "button": dom_button,
+                    "buttons": button.get_button_mask().ord(),
+                })
+            },
+        }
+    } else if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+        let keycode = if key.get_keycode() != Key::NONE { key.get_keycode() } else { key.get_physical_keycode() };
+        let physical = if key.get_physical_keycode() != Key::NONE { key.get_physical_keycode() } else { keycode };
+        let text = char::from_u32(key.get_unicode() as u32).filter(|c| !c.is_control()).map(String::from);
+        let named = NAMED_DOM_KEYS.iter().find(|(k, _, _)| *k == keycode);
+
+        let dom_key = match (&text, named) {
+            (Some(text), _) => text.clone(),
+            (None, Some((_, dom_key, _))) => dom_key.to_string(),
+            (None, None) => "Unidentified".to_string(),
+        };
+
+        json!({
+            "kind": if key.is_pressed() { "key_down" } else { "key_up" },
+            "key": dom_key,
+            "code": dom_code(physical),
+            "text": text,
+            "repeat": key.is_echo(),
+        })
+    } else if let Ok(joypad) = event.clone().try_cast::<InputEventJoypadButton>() {
+        // gamepads drive the page like arrow keys, Enter and Escape, `window.godot._input` also moves the focus
+        let (_, dom_key) = GAMEPAD_DOM_KEYS.iter().find(|(button, _)| *button == joypad.get_button_index())?;
+        json!({
+            "kind": if joypad.is_pressed() { "key_down" } else { "key_up" },
+            "key": dom_key,
+            "code": dom_key,
+            "text": null,
+            "repeat": false,
+            "gamepad": true,
+        })
+    } else if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+        // the left stick acts as a D-pad, the page turns it into arrow keys as it crosses halfway
+        let axis = match motion.get_axis() {
+            JoyAxis::LEFT_X => "x",
+            JoyAxis::LEFT_Y => "y",
+            _ => return None,
+        };
+        json!({ "kind": "joypad_motion", "axis": axis, "value": motion.get_axis_value() })
+    } else {
+        return None;
+    };
+
+    let modifiers = event.clone().try_cast::<InputEventWithModifiers>().ok();
+    let fields = dom_event.as_object_mut()?;
+    fields.insert("x".into(), json!(position.x));
+    fields.insert("y".into(), json!(position.y));
+    fields.insert("ctrlKey".into(), json!(modifiers.as_ref().is_some_and(|m| m.is_ctrl_pressed())));
+    fields.insert("shiftKey".into(), json!(modifiers.as_ref().is_some_and(|m| m.is_shift_pressed())));
+    fields.insert("altKey".into(), json!(modifiers.as_ref().is_some_and(|m| m.is_alt_pressed())));
+    fields.insert("metaKey".into(), json!(modifiers.as_ref().is_some_and(|m| m.is_meta_pressed())));
+
+    Some(dom_event)
+}
+
+/// The `KeyboardEvent.code` of a physical key, its position on a US keyboard.
+fn dom_code(key: Key) -> String {
+    match key.ord() {
+        ord @ 0x41..=0x5a => format!("Key{}", ord as u8 as char),
+        ord @ 0x30..=0x39 => format!("Digit{}", ord as u8 as char),
+        _ => NAMED_DOM_KEYS
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, _, code)| code.to_string())
+            .unwrap_or_else(|| "Unidentified".to_string()),
+    }
+}
+
+/// Pixels scrolled by a wheel notch, what browsers usually scroll by.
+const WHEEL_STEP: f32 = 100.0;
+
+/// Pixels scrolled by a line, for wheel events counted in lines.
+const WHEEL_LINE: f32 = WHEEL_STEP / 3.0;
+
+/// Gamepad buttons sent to the page as keys, with their `KeyboardEvent.key`, which is also their `code`.
+const GAMEPAD_DOM_KEYS: &[(JoyButton, &str)] = &[
+    (JoyButton::DPAD_UP, "ArrowUp"),
+    (JoyButton::DPAD_DOWN, "ArrowDown"),
+    (JoyButton::DPAD_LEFT, "ArrowLeft"),
+    (JoyButton::DPAD_RIGHT, "ArrowRight"),
+    (JoyButton::A, "Enter"),
+    (JoyButton::B, "Escape"),
+];
+
+/// Keys without text, with their `KeyboardEvent.key` and `KeyboardEvent.code`.
+const NAMED_DOM_KEYS: &[(Key, &str, &str)] = &[
+    (Key::ENTER, "Enter", "Enter"),
+    (Key::KP_ENTER, "Enter", "NumpadEnter"),
+    (Key::TAB, "Tab", "Tab"),
+    (Key::BACKSPACE, "Backspace", "Backspace"),
+    (Key::ESCAPE, "Escape", "Escape"),
+    (Key::SPACE, " ", "Space"),
+    (Key::INSERT, "Insert", "Insert"),
+    (Key::DELETE, "Delete", "Delete"),
+    (Key::HOME, "Home", "Home"),
+    (Key::END, "End", "End"),
+    (Key::PAGEUP, "PageUp", "PageUp"),
+    (Key::PAGEDOWN, "PageDown", "PageDown"),
+    (Key::UP, "ArrowUp", "ArrowUp"),
+    (Key::DOWN, "ArrowDown", "ArrowDown"),
+    (Key::LEFT, "ArrowLeft", "ArrowLeft"),
+    (Key::RIGHT, "ArrowRight", "ArrowRight"),
+    (Key::SHIFT, "Shift", "ShiftLeft"),
+    (Key::CTRL, "Control", "ControlLeft"),
+    (Key::ALT, "Alt", "AltLeft"),
+    (Key::META, "Meta", "MetaLeft"),
+    (Key::CAPSLOCK, "CapsLock", "CapsLock"),
+    (Key::MENU, "ContextMenu", "ContextMenu"),
+    (Key::MINUS, "-", "Minus"),
+    (Key::EQUAL, "=", "Equal"),
+    (Key::BRACKETLEFT, "[", "BracketLeft"),
+    (Key::BRACKETRIGHT, "]", "BracketRight"),
+    (Key::BACKSLASH, "\\", "Backslash"),
+    (Key::SEMICOLON, ";", "Semicolon"),
+    (Key::APOSTROPHE, "'", "Quote"),
+    (Key::QUOTELEFT, "`", "Backquote"),
+    (Key::COMMA, ",", "Comma"),
+    (Key::PERIOD, ".", "Period"),
+    (Key::SLASH, "/", "Slash"),
+    (Key::F1, "F1", "F1"),
+    (Key::F2, "F2", "F2"),
+    (Key::F3, "F3", "F3"),
+    (Key::F4, "F4", "F4"),
+    (Key::F5, "F5", "F5"),
+    (Key::F6, "F6", "F6"),
+    (Key::F7, "F7", "F7"),
+    (Key::F8, "F8", "F8"),
+    (Key::F9, "F9", "F9"),
+    (Key::F10, "F10", "F10"),
+    (Key::F11, "F11", "F11"),
+    (Key::F12, "F12", "F12"),
+];
+
+lazy_static! {
+    static ref GODOT_KEYS: HashMap<&'static str, Key> = HashMap::from([
+        // https://docs.godotengine.org/en/stable/classes/class_%40globalscope.html#enum-globalscope-key
+
+        ("a", Key::A),
+        ("A", Key::A),
+        ("b", Key::B),
+        ("B", Key::B),
+        ("c", Key::C),
+        ("C", Key::C),
+        ("d", Key::D),
+        ("D", Key::D),
+        ("e", Key::E),
+        ("E", Key::E),
+        ("f", Key::F),
+        ("F", Key::F),
+        ("g", Key::G),
+        ("G", Key::G),
+        ("h", Key::H),
+        ("H", Key::H),
+        ("i", Key::I),
+        ("I", Key::I),
+        ("j", Key::J),
+        ("J", Key::J),
+        ("k", Key::K),
+        ("K", Key::K),
+        ("l", Key::L),
+        ("L", Key::L),
+        ("m", Key::M),
+        ("M", Key::M),
+        ("n", Key::N),
+        ("N", Key::N),
+        ("o", Key::O),
+        ("O", Key::O),
+        ("p", Key::P),
+        ("P", Key::P),
+        ("q", Key::Q),
+        ("Q", Key::Q),
+        ("r", Key::R),
+        ("R", Key::R),
+        ("s", Key::S),
+        ("S", Key::S),
+        ("t", Key::T),
+        ("T", Key::T),
+        ("u", Key::U),
+        ("U", Key::U),
+        ("v", Key::V),
+        ("V", Key::V),
+        ("w", Key::W),
+        ("W", Key::W),
+        ("x", Key::X),
+        ("X", Key::X),
+        ("y", Key::Y),
+        ("Y", Key::Y),
+        ("z", Key::Z),
+        ("Z", Key::Z),
+        
+        ("0", Key::KEY_0),
+        ("1", Key::KEY_1),
+        ("2", Key::KEY_2),
+        ("3", Key::KEY_3),
+        ("4", Key::KEY_4),
+        ("5", Key::KEY_5),
+        ("6", Key::KEY_6),
+        ("7", Key::KEY_7),
+        ("8", Key::KEY_8),
+        ("9", Key::KEY_9),
+        ("Numpad0", Key::KP_0),
+        ("Numpad1", Key::KP_1),
+        ("Numpad2", Key::KP_2),
+        ("Numpad3", Key::KP_3),
+        ("Numpad4", Key::KP_4),
+        ("Numpad5", Key::KP_5),
+        ("Numpad6", Key::KP_6),
+        ("Numpad7", Key::KP_7),
+        ("Numpad8", Key::KP_8),
+        ("Numpad9", Key::KP_9),
+        
+        ("F1", Key::F1),
+        ("F2", Key::F2),
+        ("F3", Key::F3),
+        ("F4", Key::F4),
+        ("F5", Key::F5),
+        ("F6", Key::F6),
+        ("F7", Key::F7),
+        ("F8", Key::F8),
+        ("F9", Key::F9),
+        ("F10", Key::F10),
+        ("F11", Key::F11),
+        ("F12", Key::F12),
+        ("F13", Key::F13),
+        ("F14", Key::F14),
+        ("F15", Key::F15),
+        ("F16", Key::F16),
+        ("F17", Key::F17),
+        ("F18", Key::F18),
+        ("F19", Key::F19),
+        ("F20", Key::F20),
+        ("F21", Key::F21),
+        ("F22", Key::F22),
+        ("F23", Key::F23),
+        ("F24", Key::F24),
+        
+        ("ArrowUp", Key::UP),
+        ("ArrowDown", Key::DOWN),
+        ("ArrowLeft", Key::LEFT),
+        ("ArrowRight", Key::RIGHT),
+        
+        ("Enter", Key::ENTER),
+        ("NumpadEnter", Key::KP_ENTER),
+        ("Tab", Key::TAB),
+        ("Space", Key::SPACE),
+        (" ", Key::SPACE),
+        ("Backspace", Key::BACKSPACE),
+        ("Escape", Key::ESCAPE),
+        ("CapsLock", Key::CAPSLOCK),
+        ("ScrollLock", Key::SCROLLLOCK),
+        ("NumLock", Key::NUMLOCK),
+        ("PrintScreen", Key::PRINT),
+        ("Pause", Key::PAUSE),
+        ("Insert", Key::INSERT),
+        ("Home", Key::HOME),
+        ("PageUp", Key::PAGEUP),
+        ("Delete", Key::DELETE),
+        ("End", Key::END),
+        ("PageDown", Key::PAGEDOWN),
+        
+        ("Shift", Key::SHIFT),
+        ("Control", Key::CTRL),
+        ("Alt", Key::ALT),
+        ("AltGraph", Key::ALT),
+        ("Meta", Key::META),
+        ("ContextMenu", Key::MENU),
+        
+        ("NumpadMultiply", Key::KP_MULTIPLY),
+        ("NumpadDivide", Key::KP_DIVIDE),
+        ("NumpadAdd", Key::KP_ADD),
+        ("NumpadSubtract", Key::KP_SUBTRACT),
+        ("NumpadDecimal", Key::KP_PERIOD),
+        
+        ("MediaPlayPause", Key::MEDIAPLAY),
+        ("MediaStop", Key::MEDIASTOP),
+        ("MediaTrackNext", Key::MEDIANEXT),
+        ("MediaTrackPrevious", Key::MEDIAPREVIOUS),
+        ("VolumeDown", Key::VOLUMEDOWN),
+        ("VolumeUp", Key::VOLUMEUP),
+        ("VolumeMute", Key::VOLUMEMUTE),
+        
+        ("BrowserBack", Key::BACK),
+        ("BrowserForward", Key::FORWARD),
+        ("BrowserRefresh", Key::REFRESH),
+        ("BrowserStop", Key::STOP),
+        ("BrowserSearch", Key::SEARCH),
+        ("BrowserHome", Key::HOMEPAGE),
+        
+        ("`", Key::QUOTELEFT),
+        ("~", Key::ASCIITILDE),
+        ("!", Key::EXCLAM),
+        ("@", Key::AT),
+        ("#", Key::NUMBERSIGN),
+        ("$", Key::DOLLAR),
+        ("%", Key::PERCENT),
+        ("^", Key::ASCIICIRCUM),
+        ("&", Key::AMPERSAND),
+        ("*", Key::ASTERISK),
+        ("(", Key::PARENLEFT),
+        (")", Key::PARENRIGHT),
+        ("-", Key::MINUS),
+        ("_", Key::UNDERSCORE),
+        ("=", Key::EQUAL),
+        ("+", Key::PLUS),
+        ("[", Key::BRACKETLEFT),
+        ("{", Key::BRACELEFT),
+        ("]", Key::BRACKETRIGHT),
+        ("}", Key::BRACERIGHT),
+        ("\\", Key::BACKSLASH),
+        ("|", Key::BAR),
+        (";", Key::SEMICOLON),
+        (":", Key::COLON),
+        ("'", Key::APOSTROPHE),
+        ("\"", Key::QUOTEDBL),
+        (",", Key::COMMA),
+        ("<", Key::LESS),
+        (".", Key::PERIOD),
+        (">", Key::GREATER),
+        ("/", Key::SLASH),
+        ("?", Key::QUESTION),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(event_type: &str, key: &str, code: &str, modifiers: &[&str]) -> Value {
+        let mut event = json!({ "type": event_type, "key": key, "code": code });
+        for modifier in modifiers {
+            event[*modifier] = json!(true);
+        }
+        event
+    }
+
+    #[test]
+    fn physical_keys() {
+        let cases = [
+            ("KeyA", Key::A),
+            ("KeyZ", Key::Z),
+            ("Digit0", Key::KEY_0),
+            ("Digit1", Key::KEY_1),
+            ("Numpad0", Key::KP_0),
+            ("Numpad9", Key::KP_9),
+            ("NumpadEnter", Key::KP_ENTER),
+            ("NumpadAdd", Key::KP_ADD),
+            ("NumpadDecimal", Key::KP_PERIOD),
+            ("F1", Key::F1),
+            ("F12", Key::F12),
+            ("F13", Key::F13),
+            ("F24", Key::F24),
+            ("ShiftLeft", Key::SHIFT),
+            ("ShiftRight", Key::SHIFT),
+            ("ControlRight", Key::CTRL),
+            ("AltLeft", Key::ALT),
+            ("OSLeft", Key::META),
+            ("ArrowLeft", Key::LEFT),
+            ("Tab", Key::TAB),
+            ("Space", Key::SPACE),
+            ("Quote", Key::APOSTROPHE),
+            ("Backquote", Key::QUOTELEFT),
+            ("IntlRo", Key::NONE),
+            ("Key", Key::NONE),
+            ("KeyAB", Key::NONE),
+            ("", Key::NONE),
+        ];
+
+        for (code, expected) in cases {
+            assert_eq!(physical_key(code), expected, "{code}");
+        }
+    }
+
+    #[test]
+    fn single_chars() {
+        let cases = [
+            ("a", Some('a')),
+            ("!", Some('!')),
+            (" ", Some(' ')),
+            ("é", Some('é')),
+            ("Enter", None),
+            ("\n", None),
+            ("", None),
+        ];
+
+        for (key, expected) in cases {
+            assert_eq!(single_char(key), expected, "{key:?}");
+        }
+    }
+
+    #[test]
+    fn forwarded_keys() {
+        // (type, key, code, modifiers) => (keycode, physical keycode, unicode)
+        let cases = [
+            (("key_down", "a", "KeyA", &[][..]), (Key::A, Key::A, Some('a'))),
+            (("key_down", "A", "KeyA", &["shiftKey"][..]), (Key::A, Key::A, Some('A'))),
+            (("key_down", "Tab", "Tab", &["shiftKey"][..]), (Key::TAB, Key::TAB, None)),
+            (("key_down", "s", "KeyS", &["ctrlKey"][..]), (Key::S, Key::S, Some('s'))),
+            (("key_down", "!", "Digit1", &["shiftKey"][..]), (Key::KEY_1, Key::KEY_1, Some('!'))),
+            (("key_down", "1", "Numpad1", &[][..]), (Key::KEY_1, Key::KP_1, Some('1'))),
+            (("key_down", "Enter", "NumpadEnter", &[][..]), (Key::ENTER, Key::KP_ENTER, None)),
+            (("key_down", "F13", "F13", &[][..]), (Key::F13, Key::F13, None)),
+            (("key_down", "Dead", "IntlRo", &[][..]), (Key::NONE, Key::NONE, None)),
+            // on an AZERTY layout, the key at Q's position types "a"
+            (("key_down", "a", "KeyQ", &[][..]), (Key::A, Key::Q, Some('a'))),
+        ];
+
+        for ((event_type, key, code, modifiers), (keycode, physical_keycode, unicode)) in cases {
+            let forwarded = forwarded_key(&key_event(event_type, key, code, modifiers));
+            assert_eq!(forwarded.keycode, keycode, "{key} ({code})");
+            assert_eq!(forwarded.physical_keycode, physical_keycode, "{key} ({code})");
+            assert_eq!(forwarded.unicode, unicode, "{key} ({code})");
+        }
+    }
+
+    #[test]
+    fn forwarded_modifiers() {
+        let forwarded = forwarded_key(&key_event("key_down", "Tab", "Tab", &["shiftKey"]));
+        assert!(forwarded.shift && !forwarded.ctrl && !forwarded.alt && !forwarded.meta);
+
+        let forwarded = forwarded_key(&key_event("key_down", "s", "KeyS", &["ctrlKey"]));
+        assert!(forwarded.ctrl && !forwarded.shift);
+
+        let forwarded = forwarded_key(&key_event("key_down", "s", "KeyS", &["altKey", "metaKey"]));
+        assert!(forwarded.alt && forwarded.meta && !forwarded.ctrl);
+    }
+
+    #[test]
+    fn forwarded_key_state() {
+        let forwarded = forwarded_key(&key_event("key_down", "a", "KeyA", &["repeat"]));
+        assert!(forwarded.pressed && forwarded.echo);
+
+        let forwarded = forwarded_key(&key_event("key_up", "a", "KeyA", &[]));
+        assert!(!forwarded.pressed && !forwarded.echo);
+    }
+}
@@ -0,0 +1,111 @@
+use godot::prelude::*;
+use serde_json::{Map, Number, Value};
+
+pub fn json_to_variant(value: &Value) -> Variant {
+    match value {
+        Value::Null => Variant::nil(),
+        Value::Bool(b) => b.to_variant(),
+        Value::Number(n) => {
+            // keep integers as integers, Godot's own JSON parser turns everything into floats
+            if let Some(i) = n.as_i64() {
+                i.to_variant()
+            } else {
+                n.as_f64().unwrap_or(0.0).to_variant()
+            }
+        }
+        Value::String(s) => GString::from(s.as_str()).to_variant(),
+        Value::Array(values) => {
+            let mut array = VariantArray::new();
+            for value in values {
+                array.push(&json_to_variant(value));
+            }
+            array.to_variant()
+        }
+        Value::Object(map) => {
+            let mut dictionary = Dictionary::new();
+            for (key, value) in map {
+                dictionary.set(GString::from(key.as_str()), json_to_variant(value));
+            }
+            dictionary.to_variant()
+        }
+    }
+}
+
+pub fn variant_to_json(variant: &Variant) -> Value {
+    match variant.get_type() {
+        VariantType::NIL => Value::Null,
+        VariantType::BOOL => Value::Bool(variant.to::<bool>()),
+        VariantType::INT => Value::from(variant.to::<i64>()),
+        VariantType::FLOAT => Number::from_f64(variant.to::<f64>())
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        VariantType::STRING | VariantType::STRING_NAME | VariantType::NODE_PATH => {
+            Value::String(variant.stringify().to_string())
+        }
+        VariantType::DICTIONARY => {
+            let mut map = Map::new();
+            for (key, value) in variant.to::<Dictionary>().iter_shared() {
+                map.insert(key.stringify().to_string(), variant_to_json(&value));
+            }
+            Value::Object(map)
+        }
+        VariantType::ARRAY => Value::Array(
+            variant
+                .to::<VariantArray>()
+                .iter_shared()
+                .map(|value| variant_to_json(&value))
+                .collect(),
+        ),
+        VariantType::PACKED_BYTE_ARRAY => Value::Array(
+            variant
+                .to::<PackedByteArray>()
+                .as_slice()
+                .iter()
+                .map(|byte| Value::from(*byte))
+                .collect(),
+        ),
+        VariantType::PACKED_INT32_ARRAY => Value::Array(
+            variant
+                .to::<PackedInt32Array>()
+                .as_slice()
+                .iter()
+                .map(|i| Value::from(*i))
+                .collect(),
+        ),
+        VariantType::PACKED_INT64_ARRAY => Value::Array(
+            variant
+                .to::<PackedInt64Array>()
+                .as_slice()
+                .iter()
+                .map(|i| Value::from(*i))
+                .collect(),
+        ),
+        VariantType::PACKED_FLOAT32_ARRAY => Value::Array(
+            variant
+                .to::<PackedFloat32Array>()
+                .as_slice()
+                .iter()
+                .map(|f| Value::from(*f as f64))
+                .collect(),
+        ),
+        VariantType::PACKED_FLOAT64_ARRAY => Value::Array(
+            variant
+                .to::<PackedFloat64Array>()
+                .as_slice()
+                .iter()
+                .map(|f| Value::from(*f))
+                .collect(),
+        ),
+        VariantType::PACKED_STRING_ARRAY => Value::Array(
+            variant
+                .to::<PackedStringArray>()
+                .as_slice()
+                .iter()
+                .map(|s| Value::String(s.to_string()))
+                .collect(),
+        ),
+        // anything else (vectors, colors, objects...) is sent using its string representation
+        _ => Value::String(variant.stringify().to_string()),
+    }
+}
+
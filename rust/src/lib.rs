@@ -1,21 +1,43 @@
+mod cookies;
 mod godot_window;
+mod input;
+mod json;
+mod navigation;
+mod offscreen;
+mod origin;
+mod pool;
 mod protocols;
+mod proxy;
+mod snapshot;
+mod state;
+mod web_context;
 
-use godot::global::MouseButtonMask;
 use godot::init::*;
 use godot::prelude::*;
-use godot::classes::{Control, DisplayServer, IControl, Input, InputEventMouseButton, InputEventMouseMotion, InputEventKey};
-use godot::global::{Key, MouseButton};
-use lazy_static::lazy_static;
+use godot::classes::notify::ControlNotification;
+use godot::classes::{Control, Crypto, DisplayServer, IControl, Image, ImageTexture, InputEvent, InputEventMouse, ProjectSettings};
 use serde_json;
-use std::collections::HashMap;
-use std::sync::Mutex;
-use wry::{WebViewBuilder, Rect, WebViewAttributes};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use wry::{PageLoadEvent, WebViewBuilder, Rect, WebViewAttributes};
 use wry::dpi::{PhysicalPosition, PhysicalSize};
 use wry::http::Request;
 
+use crate::cookies::{cookie_to_dictionary, CookieParams};
 use crate::godot_window::GodotWindow;
-use crate::protocols::get_res_response;
+use crate::input::{dom_input_event, forwarded_input_events, InputQueue, PointerState};
+use crate::json::{json_to_variant, variant_to_json};
+use crate::offscreen::OffscreenHost;
+use crate::origin::{origin_of, OriginPermissions};
+use crate::pool::{add_pooled_webview, pooled_webview_count, take_pooled_webview, PooledWebView};
+use crate::protocols::{handle_ipc_request, handle_res_request};
+use crate::proxy::parse_proxy_url;
+use crate::state::{next_webview_id, register_webview_state, unregister_webview_state, webview_state, WebViewState};
+use crate::web_context::{claim_proxy, shared_web_context, ProxyClaim};
 
 #[cfg(target_os = "windows")]
 use {
@@ -32,44 +54,131 @@ extern "system" {}
 
 struct GodotWRY;
 
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq, Debug)]
+#[godot(via = i64)]
+enum QueueDropPolicy {
+    DropOldest,
+    DropNewest,
+}
+
 #[gdextension]
-unsafe impl ExtensionLibrary for GodotWRY {}
+unsafe impl ExtensionLibrary for GodotWRY {
+    fn on_level_deinit(level: InitLevel) {
+        if level == InitLevel::Scene {
+            pool::clear_pool();
+        }
+    }
+}
 
 #[derive(GodotClass)]
 #[class(base=Control)]
 struct WebView {
     base: Base<Control>,
     webview: Option<wry::WebView>,
-    previous_screen_position: Vector2,
+    webview_id: String,
+    offscreen_host: Option<OffscreenHost>,
+    proxy_claim: Option<ProxyClaim>,
+    texture: Gd<ImageTexture>,
+    time_since_frame: f64,
+    previous_window_rect: Rect2,
+    webview_origin: Rc<Cell<Vector2>>,
     previous_viewport_size: Vector2i,
+    previous_content_scale_factor: f32,
+    rpc_handlers: HashMap<String, Callable>,
+    js_rpc_callbacks: RefCell<HashMap<i64, Callable>>,
+    next_js_rpc_id: Cell<i64>,
+    event_handlers: HashMap<String, Vec<Callable>>,
+    outgoing_binary: Arc<Mutex<HashMap<i64, Vec<u8>>>>,
+    next_binary_id: Cell<i64>,
+    announced_binary_id: Arc<AtomicI64>,
+    page_ready: Arc<AtomicBool>,
+    outbound_queue: RefCell<VecDeque<String>>,
+    input_queue: Rc<RefCell<InputQueue>>,
+    time_since_input_flush: f64,
+    rebuild_pending: bool,
+    recreate_on_enter: bool,
+    restore_url: Option<String>,
+    current_url: RefCell<String>,
+    reported_history: Rc<Cell<(Option<bool>, Option<bool>)>>,
+    #[export]
+    create_on_ready: bool,
     #[export]
+    #[var(get, set = set_full_window_size)]
     full_window_size: bool,
     #[export]
+    #[var(get, set = set_url)]
     url: GString,
     #[export]
+    #[var(get, set = set_html)]
     html: GString,
     #[export]
+    #[var(get, set = set_transparent)]
     transparent: bool,
     #[export]
+    #[var(get, set = set_offscreen)]
+    offscreen: bool,
+    #[export]
+    #[var(get, set = set_offscreen_size)]
+    offscreen_size: Vector2i,
+    #[export]
+    offscreen_fps: f64,
+    #[export]
+    #[var(get, set = set_background_color)]
     background_color: Color,
     #[export]
+    #[var(get, set = set_devtools)]
     devtools: bool,
     #[export]
     headers: Dictionary,
     #[export]
+    #[var(get, set = set_user_agent)]
     user_agent: GString,
     #[export]
+    #[var(get, set = set_data_directory)]
+    data_directory: GString,
+    #[export]
+    #[var(get, set = set_proxy_url)]
+    proxy_url: GString,
+    #[export]
+    #[var(get, set = set_zoom_hotkeys)]
     zoom_hotkeys: bool,
     #[export]
+    #[var(get = get_zoom, set = set_zoom)]
+    zoom: f64,
+    #[export]
+    #[var(get, set = set_zoom_follows_content_scale)]
+    zoom_follows_content_scale: bool,
+    #[export]
+    #[var(get, set = set_clipboard)]
     clipboard: bool,
     #[export]
+    #[var(get, set = set_incognito)]
     incognito: bool,
     #[export]
     focused_when_created: bool,
     #[export]
+    #[var(get, set = set_forward_input_events)]
     forward_input_events: bool,
     #[export]
+    #[var(get, set = set_coalesce_mouse_motion)]
+    coalesce_mouse_motion: bool,
+    #[export]
+    input_flush_rate: f64,
+    #[export]
+    #[var(get, set = set_autoplay)]
     autoplay: bool,
+    #[export]
+    #[var(get, set = set_trusted_origins)]
+    trusted_origins: PackedStringArray,
+    #[export]
+    #[var(get, set = set_origin_permissions)]
+    origin_permissions: Dictionary,
+    #[export]
+    queue_until_ready: bool,
+    #[export]
+    max_queue_size: i32,
+    #[export]
+    queue_drop_policy: QueueDropPolicy,
 }
 
 #[godot_api]
@@ -78,50 +187,165 @@ impl IControl for WebView {
         Self {
             base,
             webview: None,
-            previous_screen_position: Vector2::default(),
+            webview_id: String::new(),
+            offscreen_host: None,
+            proxy_claim: None,
+            texture: ImageTexture::new_gd(),
+            time_since_frame: 0.0,
+            previous_window_rect: Rect2::default(),
+            webview_origin: Rc::new(Cell::new(Vector2::ZERO)),
             previous_viewport_size: Vector2i::default(),
+            previous_content_scale_factor: 1.0,
+            rpc_handlers: HashMap::new(),
+            js_rpc_callbacks: RefCell::new(HashMap::new()),
+            next_js_rpc_id: Cell::new(0),
+            event_handlers: HashMap::new(),
+            outgoing_binary: Arc::new(Mutex::new(HashMap::new())),
+            next_binary_id: Cell::new(0),
+            announced_binary_id: Arc::new(AtomicI64::new(0)),
+            page_ready: Arc::new(AtomicBool::new(false)),
+            outbound_queue: RefCell::new(VecDeque::new()),
+            input_queue: Rc::new(RefCell::new(InputQueue::default())),
+            time_since_input_flush: 0.0,
+            rebuild_pending: false,
+            recreate_on_enter: false,
+            restore_url: None,
+            current_url: RefCell::new(String::new()),
+            reported_history: Rc::new(Cell::new((None, None))),
+            create_on_ready: true,
             full_window_size: true,
             url: "https://github.com/doceazedo/godot_wry".into(),
             html: "".into(),
             transparent: false,
+            offscreen: false,
+            offscreen_size: Vector2i::new(1280, 720),
+            offscreen_fps: 30.0,
             background_color: Color::from_rgb(1.0, 1.0, 1.0),
             devtools: true,
             headers: Dictionary::new(),
             user_agent: "".into(),
+            data_directory: "".into(),
+            proxy_url: "".into(),
             zoom_hotkeys: false,
+            zoom: 1.0,
+            zoom_follows_content_scale: false,
             clipboard: true,
             incognito: false,
             focused_when_created: true,
             forward_input_events: true,
+            coalesce_mouse_motion: true,
+            input_flush_rate: 0.0,
             autoplay: false,
+            trusted_origins: PackedStringArray::new(),
+            origin_permissions: Dictionary::new(),
+            queue_until_ready: true,
+            max_queue_size: 256,
+            queue_drop_policy: QueueDropPolicy::DropOldest,
         }
     }
 
     fn ready(&mut self) {
-        self.create_webview();
+        if self.create_on_ready {
+            self.create_webview();
+        }
+    }
+
+    fn enter_tree(&mut self) {
+        // `ready` only runs once, a node that was removed and added back recreates its webview here
+        if self.recreate_on_enter {
+            self.recreate_on_enter = false;
+            self.base_mut().call_deferred("create_webview", &[]);
+        }
+    }
+
+    fn exit_tree(&mut self) {
+        if self.webview.is_none() {
+            return;
+        }
+
+        self.recreate_on_enter = true;
+        self.remember_url();
+        self.teardown_webview();
+    }
+
+    fn on_notification(&mut self, what: ControlNotification) {
+        if what == ControlNotification::PREDELETE {
+            self.destroy_webview();
+        }
     }
 
-    fn process(&mut self, _delta: f64) {
+    fn process(&mut self, delta: f64) {
         self.update_webview();
+        self.update_offscreen(delta);
+        self.flush_input_events(delta);
+    }
+
+    fn draw(&mut self) {
+        if self.offscreen_host.is_none() {
+            return;
+        }
+
+        let texture = self.texture.clone();
+        let rect = Rect2::new(Vector2::ZERO, self.base().get_size());
+        self.base_mut().draw_texture_rect(&texture, rect, false);
+    }
+
+    fn gui_input(&mut self, event: Gd<InputEvent>) {
+        // an offscreen page drawn by this control gets the input it receives
+        if self.offscreen_host.is_none() {
+            return;
+        }
+
+        let size = self.base().get_size();
+        let position = match event.clone().try_cast::<InputEventMouse>() {
+            Ok(mouse) if size.x > 0.0 && size.y > 0.0 => mouse.get_position() / size,
+            _ => Vector2::ZERO,
+        };
+        self.send_input_at_uv(event, position);
+        self.base_mut().accept_event();
     }
 }
 
 #[godot_api]
 impl WebView {
     #[signal]
-    fn ipc_message(message: GString);
+    fn ipc_message(message: GString, origin: GString);
+
+    #[signal]
+    fn untrusted_ipc_message(origin: GString, message: GString);
+
+    #[signal]
+    fn permission_denied(origin: GString, capability: GString);
+
+    #[signal]
+    fn binary_message(data: PackedByteArray);
+
+    #[signal]
+    fn message_received(message: Variant);
+
+    #[signal]
+    fn url_changed(url: GString);
 
     #[func]
     fn update_webview(&mut self) {
         if let Some(_) = &self.webview {
             let viewport_size = self.base().get_tree().expect("Could not get tree").get_root().expect("Could not get viewport").get_size();
 
-            if self.base().get_screen_position() != self.previous_screen_position || viewport_size != self.previous_viewport_size {
-                self.previous_screen_position = self.base().get_screen_position();
+            let window_rect = self.window_rect();
+            if window_rect != self.previous_window_rect || viewport_size != self.previous_viewport_size {
+                self.previous_window_rect = window_rect;
                 self.previous_viewport_size = viewport_size;
                 self.resize();
             }
 
+            if self.zoom_follows_content_scale {
+                let content_scale_factor = self.base().get_tree().expect("Could not get tree").get_root().expect("Could not get viewport").get_content_scale_factor();
+                if content_scale_factor != self.previous_content_scale_factor {
+                    self.previous_content_scale_factor = content_scale_factor;
+                    self.apply_zoom();
+                }
+            }
+
             #[cfg(target_os = "linux")]
             while gtk::events_pending() {
                 gtk::main_iteration_do(false);
@@ -131,567 +355,1266 @@ impl WebView {
 
     #[func]
     fn create_webview(&mut self) {
-        let display_server = DisplayServer::singleton();
-        if display_server.get_name() == "headless".into()
-        {
-            godot_warn!("Godot WRY: Headless mode detected. webview will not be created.");
+        // the native webview lives in the window, so wait until the node is in the tree
+        if !self.base().is_inside_tree() {
+            self.recreate_on_enter = true;
             return;
         }
 
-        let window = GodotWindow;
-
-        // remove WS_CLIPCHILDREN from the window style
-        // otherwise, transparent on windows won't work
-        #[cfg(target_os = "windows")]
-        {
-            let handle = window.window_handle().unwrap().as_raw();
-            let raw_handle: HWND = match handle {
-                RawWindowHandle::Win32(win32) => HWND(win32.hwnd.get() as _),
-                _ => {
-                    panic!("Unsupported window handle type");
-                }
-            };
-
-            unsafe {
-                let current_style = GetWindowLongPtrA(raw_handle, GWL_STYLE);
-                // remove WS_CLIPCHILDREN
-                SetWindowLongPtrA(raw_handle, GWL_STYLE, current_style & !0x02000000);
-            };
+        if self.webview.is_some() {
+            self.teardown_webview();
         }
 
-        let base = self.base().clone();
-        let webview_builder = WebViewBuilder::with_attributes(WebViewAttributes {
-            url: if self.html.is_empty() { Some(String::from(&self.url)) } else { None },
-            html: if self.url.is_empty() { Some(String::from(&self.html)) } else { None },
-            transparent: self.transparent,
-            devtools: self.devtools,
-            // headers: Some(HeaderMap::try_from(self.headers.iter_shared().typed::<GString, Variant>()).unwrap_or_default()),
-            user_agent: Some(String::from(&self.user_agent)),
-            zoom_hotkeys_enabled: self.zoom_hotkeys,
-            clipboard: self.clipboard,
-            incognito: self.incognito,
-            focused: self.focused_when_created,
-            autoplay: self.autoplay,
-            accept_first_mouse: true,
-            ..Default::default()
-        })
-            .with_ipc_handler(move |req: Request<String>| {
-                let body = req.body().as_str();
-                
-                if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(body) {
-                    if let Some(event_type) = json_value.get("type").and_then(|t| t.as_str()) {
-                        match event_type {
-                            "_mouse_move" => {
-                                let x = json_value.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
-                                let y = json_value.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
-                                
-                                let movement_x = json_value.get("movementX").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
-                                let movement_y = json_value.get("movementY").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
-                                
-                                let mut event = InputEventMouseMotion::new_gd();
-                                event.set_position(Vector2::new(x, y));
-                                event.set_global_position(Vector2::new(x, y));
-                                
-                                let button_mask = CURRENT_BUTTON_MASK.lock().unwrap();
-                                event.set_button_mask(*button_mask);
-
-                                event.set_relative(Vector2::new(movement_x, movement_y));
-                                
-                                Input::singleton().parse_input_event(&event);
-                                return;
-                            },
-                            
-                            "_mouse_down" | "_mouse_up" => {
-                                let x = json_value.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
-                                let y = json_value.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32;
-                                let button = json_value.get("button").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
-                                
-                                let godot_button = match button {
-                                    0 => MouseButton::LEFT,
-                                    1 => MouseButton::MIDDLE,
-                                    2 => MouseButton::RIGHT,
-                                    3 => MouseButton::WHEEL_UP,
-                                    4 => MouseButton::WHEEL_DOWN,
-                                    _ => MouseButton::LEFT, // default to left button
-                                };
-                                
-                                let pressed = event_type == "_mouse_down";
-                                let mask = match godot_button {
-                                    MouseButton::LEFT => MouseButtonMask::LEFT,
-                                    MouseButton::RIGHT => MouseButtonMask::RIGHT,
-                                    MouseButton::MIDDLE => MouseButtonMask::MIDDLE,
-                                    _ => MouseButtonMask::default(),
-                                };
-                                
-                                if godot_button != MouseButton::WHEEL_UP && godot_button != MouseButton::WHEEL_DOWN {
-                                    let mut button_mask = CURRENT_BUTTON_MASK.lock().unwrap();
-                                    if pressed {
-                                        *button_mask = *button_mask | mask;
-                                    } else {
-                                        match godot_button {
-                                            MouseButton::LEFT => {
-                                                if button_mask.is_set(MouseButtonMask::LEFT) {
-                                                    *button_mask = MouseButtonMask::from_ord(button_mask.ord() & !MouseButtonMask::LEFT.ord());
-                                                }
-                                            },
-                                            MouseButton::RIGHT => {
-                                                if button_mask.is_set(MouseButtonMask::RIGHT) {
-                                                    *button_mask = MouseButtonMask::from_ord(button_mask.ord() & !MouseButtonMask::RIGHT.ord());
-                                                }
-                                            },
-                                            MouseButton::MIDDLE => {
-                                                if button_mask.is_set(MouseButtonMask::MIDDLE) {
-                                                    *button_mask = MouseButtonMask::from_ord(button_mask.ord() & !MouseButtonMask::MIDDLE.ord());
-                                                }
-                                            },
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                                
-                                let mut event = InputEventMouseButton::new_gd();
-                                event.set_button_index(godot_button);
-                                event.set_position(Vector2::new(x, y));
-                                event.set_global_position(Vector2::new(x, y));
-                                event.set_pressed(pressed);
-                                
-                                let button_mask = CURRENT_BUTTON_MASK.lock().unwrap();
-                                event.set_button_mask(*button_mask);
-                                
-                                Input::singleton().parse_input_event(&event);
-                                return;
-                            },
-                            
-                            "_key_down" | "_key_up" => {
-                                let key_str = json_value.get("key").and_then(|v| v.as_str()).unwrap_or("");
-                                // let key_code = json_value.get("keyCode").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
-                                
-                                let mut event = InputEventKey::new_gd();
-                                
-                                let godot_key = GODOT_KEYS.get(key_str).copied().unwrap_or(Key::NONE);
-                                
-                                event.set_keycode(godot_key);
-                                event.set_pressed(event_type == "_key_down");
-                                
-                                Input::singleton().parse_input_event(&event);
-                                return;
-                            },
-                            
-                            _ => {}
-                        }
-                    }
-                }
-                
-                // if we get here, this is a regular IPC message
-                base.clone().emit_signal("ipc_message", &[body.to_variant()]);
-            })
-            .with_custom_protocol(
-                "res".into(), move |_webview_id, request| get_res_response(request),
-            );
+        if is_headless() {
+            godot_warn!("Godot WRY: Headless mode detected. webview will not be created.");
+            return;
+        }
 
         if !self.url.is_empty() && !self.html.is_empty() {
             godot_error!("[Godot WRY] You have entered both a URL and HTML code. You may only enter one at a time.")
         }
 
-        let webview = webview_builder.build_as_child(&window).unwrap();
-        self.webview.replace(webview);
+        let url = self.restore_url.take().unwrap_or_else(|| String::from(&self.url));
+        let html = String::from(&self.html);
 
-        let mut viewport = self.base().get_tree().expect("Could not get tree").get_root().expect("Could not get viewport");
-        viewport.connect("size_changed", &Callable::from_object_method(&*self.base(), "resize"));
-
-        self.base().clone().connect("resized", &Callable::from_object_method(&*self.base(), "resize"));
-        self.base().clone().connect("visibility_changed", &Callable::from_object_method(&*self.base(), "update_visibility"));
-
-        if self.forward_input_events {
-            let forward_script = r#"
-                document.addEventListener('mousemove', (e) => {
-                    if (!document.hasFocus()) return;
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_mouse_move',
-                        x: e.clientX * window.devicePixelRatio,
-                        y: e.clientY * window.devicePixelRatio,
-                        movementX: e.movementX * window.devicePixelRatio,
-                        movementY: e.movementY * window.devicePixelRatio,
-                        button: e.button
-                    }));
-                });
-                document.addEventListener('mousedown', (e) => {
-                    if (!document.hasFocus()) return;
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_mouse_down',
-                        x: e.clientX * window.devicePixelRatio,
-                        y: e.clientY * window.devicePixelRatio,
-                        button: e.button
-                    }));
-                });
-                document.addEventListener('mouseup', (e) => {
-                    if (!document.hasFocus()) return;
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_mouse_up', 
-                        x: e.clientX * window.devicePixelRatio,
-                        y: e.clientY * window.devicePixelRatio,
-                        button: e.button
-                    }));
-                });
-                document.addEventListener('wheel', (e) => {
-                    if (!document.hasFocus()) return;
-                    const button = e.deltaY < 0 ? 3 : 4; // 3 = WHEEL_UP, 4 = WHEEL_DOWN
-                    
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_mouse_down',
-                        x: e.clientX * window.devicePixelRatio,
-                        y: e.clientY * window.devicePixelRatio,
-                        button: button
-                    }));
-                    
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_mouse_up',
-                        x: e.clientX * window.devicePixelRatio,
-                        y: e.clientY * window.devicePixelRatio,
-                        button: button
-                    }));
-                });
-                document.addEventListener('keydown', (e) => {
-                    if (!document.hasFocus()) return;
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_key_down',
-                        key: e.key,
-                        code: e.code,
-                        keyCode: e.keyCode
-                    }));
-                });
-                document.addEventListener('keyup', (e) => {
-                    if (!document.hasFocus()) return;
-                    window.ipc.postMessage(JSON.stringify({
-                        type: '_key_up',
-                        key: e.key,
-                        code: e.code,
-                        keyCode: e.keyCode
-                    }));
-                });
-            "#;
-            
-            if let Some(ref webview) = self.webview {
-                let _ = webview.evaluate_script(forward_script);
+        if self.offscreen {
+            self.offscreen_host = OffscreenHost::new(self.offscreen_size);
+            if self.offscreen_host.is_none() {
+                godot_warn!("[Godot WRY] Offscreen rendering is only supported on Linux, creating a regular webview instead.");
+            }
+        }
+
+        let pooled = if self.offscreen_host.is_none() { take_pooled_webview(&self.creation_key()) } else { None };
+        if let Some(pooled) = pooled {
+            // pooled webviews wait hidden on about:blank
+            register_webview_state(&pooled.id, self.webview_state());
+            if html.is_empty() {
+                let _ = pooled.webview.load_url(&url);
+            } else {
+                let _ = pooled.webview.load_html(&html);
+            }
+            let _ = pooled.webview.set_background_color(self.background_rgba());
+            let _ = pooled.webview.set_visible(self.base().is_visible_in_tree());
+            if self.focused_when_created {
+                let _ = pooled.webview.focus();
+            }
+
+            self.webview_id = pooled.id;
+            self.webview.replace(pooled.webview);
+            self.proxy_claim = pooled.proxy_claim;
+        } else {
+            let webview_id = next_webview_id();
+            register_webview_state(&webview_id, self.webview_state());
+
+            let url = if html.is_empty() { Some(url) } else { None };
+            let html = if self.url.is_empty() { Some(html) } else { None };
+            match self.build_webview(&webview_id, url, html, true, self.offscreen_host.as_ref()) {
+                Some((webview, proxy_claim)) => {
+                    self.webview_id = webview_id;
+                    self.webview.replace(webview);
+                    self.proxy_claim = proxy_claim;
+                },
+                None => {
+                    unregister_webview_state(&webview_id);
+                    self.offscreen_host = None;
+                    return;
+                },
             }
         }
 
+        // a rebuilt webview is still connected from its first creation
+        let resize = Callable::from_object_method(&*self.base(), "resize");
+        let update_visibility = Callable::from_object_method(&*self.base(), "update_visibility");
+        let mut viewport = self.base().get_tree().expect("Could not get tree").get_root().expect("Could not get viewport");
+        if !viewport.is_connected("size_changed", &resize) {
+            viewport.connect("size_changed", &resize);
+        }
+        if !self.base().is_connected("resized", &resize) {
+            self.base().clone().connect("resized", &resize);
+        }
+        if !self.base().is_connected("visibility_changed", &update_visibility) {
+            self.base().clone().connect("visibility_changed", &update_visibility);
+        }
+
+        self.apply_zoom();
         self.resize()
     }
 
     #[func]
-    fn post_message(&self, message: GString) {
-        if let Some(webview) = &self.webview {
-            let data = serde_json::json!({ "detail": String::from(message) });
-            let script = format!("document.dispatchEvent(new CustomEvent('message', {}))", data);
-            let _ = webview.evaluate_script(&script);
-        }
+    fn set_full_window_size(&mut self, full_window_size: bool) {
+        self.full_window_size = full_window_size;
+        self.resize();
     }
 
     #[func]
-    fn resize(&self) {
-        if let Some(webview) = &self.webview {
-            let rect = if self.full_window_size {
-                let viewport_size = self.base().get_tree().expect("Could not get tree").get_root().expect("Could not get viewport").get_size();
-                Rect {
-                    position: PhysicalPosition::new(0, 0).into(),
-                    size: PhysicalSize::new(viewport_size.x, viewport_size.y).into(),
-                }
-            } else {
-                let pos = self.base().get_screen_position();
-                let size = self.base().get_size();
-                Rect {
-                    position: PhysicalPosition::new(pos.x, pos.y).into(),
-                    size: PhysicalSize::new(size.x, size.y).into(),
-                }
-            };
-            let _ = webview.set_bounds(rect);
+    fn set_url(&mut self, url: GString) {
+        self.url = url.clone();
+        if !url.is_empty() {
+            // the last one assigned wins, including when the webview is rebuilt
+            self.html = GString::new();
+            self.load_url(url);
         }
     }
 
     #[func]
-    fn eval(&self, script: GString) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.evaluate_script(&*String::from(script));
+    fn set_html(&mut self, html: GString) {
+        self.html = html.clone();
+        if !html.is_empty() {
+            self.url = GString::new();
+            self.load_html(html);
         }
     }
 
     #[func]
-    fn update_visibility(&self) {
-        if let Some(webview) = &self.webview {
-            let visibility = self.base().is_visible_in_tree();
-            webview.set_visible(visibility).expect("Could not set visibility");
-            self.resize()
-        }
+    fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+        self.rebuild_webview();
     }
 
     #[func]
-    fn set_visible(&self, visibility: bool) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.set_visible(visibility);
-        }
+    fn set_devtools(&mut self, devtools: bool) {
+        self.devtools = devtools;
+        self.rebuild_webview();
     }
 
     #[func]
-    fn load_html(&self, html: GString) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.load_html(&*String::from(html));
-        }
+    fn set_user_agent(&mut self, user_agent: GString) {
+        self.user_agent = user_agent;
+        self.rebuild_webview();
     }
 
     #[func]
-    fn load_url(&self, url: GString) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.load_url(&*String::from(url));
-        }
+    fn set_data_directory(&mut self, data_directory: GString) {
+        self.data_directory = data_directory;
+        self.rebuild_webview();
     }
 
     #[func]
-    fn clear_all_browsing_data(&self) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.clear_all_browsing_data();
-        }
+    fn set_proxy_url(&mut self, proxy_url: GString) {
+        self.proxy_url = proxy_url;
+        self.rebuild_webview();
     }
 
     #[func]
-    fn close_devtools(&self) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.close_devtools();
+    fn set_zoom_hotkeys(&mut self, zoom_hotkeys: bool) {
+        self.zoom_hotkeys = zoom_hotkeys;
+        self.rebuild_webview();
+    }
+
+    #[func]
+    fn set_clipboard(&mut self, clipboard: bool) {
+        self.clipboard = clipboard;
+        self.rebuild_webview();
+    }
+
+    #[func]
+    fn set_incognito(&mut self, incognito: bool) {
+        self.incognito = incognito;
+        self.rebuild_webview();
+    }
+
+    #[func]
+    fn set_forward_input_events(&mut self, forward_input_events: bool) {
+        self.forward_input_events = forward_input_events;
+        self.rebuild_webview();
+    }
+
+    #[func]
+    fn set_coalesce_mouse_motion(&mut self, coalesce_mouse_motion: bool) {
+        self.coalesce_mouse_motion = coalesce_mouse_motion;
+        self.update_webview_state();
+    }
+
+    #[func]
+    fn set_autoplay(&mut self, autoplay: bool) {
+        self.autoplay = autoplay;
+        self.rebuild_webview();
+    }
+
+    #[func]
+    fn set_trusted_origins(&mut self, trusted_origins: PackedStringArray) {
+        self.trusted_origins = trusted_origins;
+        self.update_webview_state();
+    }
+
+    #[func]
+    fn set_origin_permissions(&mut self, origin_permissions: Dictionary) {
+        self.origin_permissions = origin_permissions;
+        self.update_webview_state();
+    }
+
+    #[func]
+    fn _rebuild_webview(&mut self) {
+        self.rebuild_pending = false;
+        if self.webview.is_none() {
+            return;
         }
+
+        self.remember_url();
+        self.teardown_webview();
+        self.create_webview();
     }
 
+    /// Builds `count` hidden webviews with this node's settings, ready to be adopted by `create_webview()`
+    /// on any `WebView` whose creation settings match. This node doesn't need to be in the tree.
     #[func]
-    fn open_devtools(&self) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.open_devtools();
+    fn prewarm(&self, count: i32) {
+        if is_headless() {
+            return;
+        }
+        if self.offscreen {
+            godot_warn!("[Godot WRY] Offscreen webviews can't be pooled.");
+            return;
+        }
+
+        let key = self.creation_key();
+        for _ in 0..count {
+            let webview_id = next_webview_id();
+            match self.build_webview(&webview_id, Some("about:blank".into()), None, false, None) {
+                Some((webview, proxy_claim)) => add_pooled_webview(PooledWebView { key: key.clone(), id: webview_id, webview, proxy_claim }),
+                None => return,
+            }
         }
     }
 
+    /// Number of pooled webviews this node could adopt.
     #[func]
-    fn is_devtools_open(&self) -> bool {
-        if let Some(webview) = &self.webview {
-            return webview.is_devtools_open();
+    fn get_pooled_count(&self) -> i32 {
+        pooled_webview_count(&self.creation_key()) as i32
+    }
+
+    /// Closes every webview waiting in the pool.
+    #[func]
+    fn clear_pool() {
+        pool::clear_pool();
+    }
+
+    /// Closes the native webview and releases everything tied to it. Queued messages and pending `invoke_js` callbacks are dropped.
+    /// It can be created again with `create_webview()`.
+    #[func]
+    fn destroy_webview(&mut self) {
+        self.teardown_webview();
+        self.recreate_on_enter = false;
+        self.rebuild_pending = false;
+        self.restore_url = None;
+        self.outbound_queue.borrow_mut().clear();
+        self.js_rpc_callbacks.borrow_mut().clear();
+        self.outgoing_binary.lock().unwrap().clear();
+        self.current_url.borrow_mut().clear();
+        self.reported_history.set((None, None));
+    }
+
+    #[func]
+    fn set_offscreen(&mut self, offscreen: bool) {
+        self.offscreen = offscreen;
+        self.rebuild_webview();
+    }
+
+    #[func]
+    fn set_offscreen_size(&mut self, offscreen_size: Vector2i) {
+        self.offscreen_size = offscreen_size;
+        self.resize();
+    }
+
+    /// The texture an offscreen webview renders into. It's updated in place, so it can be assigned once.
+    #[func]
+    fn get_texture(&self) -> Gd<ImageTexture> {
+        self.texture.clone()
+    }
+
+    /// Sends `event` to the page at `uv`, from (0, 0) at the top left to (1, 1) at the bottom right.
+    /// For a native webview, that's `uv` of this control, wherever the page is in the window.
+    #[func]
+    fn send_input_at_uv(&self, event: Gd<InputEvent>, uv: Vector2) {
+        if self.offscreen_host.is_some() {
+            let position = uv * self.offscreen_size.cast_float() / self.effective_zoom() as f32;
+            self.dispatch_input_event(&event, position, false);
+            return;
         }
-        false
+
+        // the page lays out in window pixels from the webview's corner, it scales them into CSS pixels itself
+        let position = self.window_transform() * (uv * self.base().get_size()) - self.webview_origin.get();
+        self.dispatch_input_event(&event, position, true);
     }
 
+    /// Sends `event` to the page. Mouse positions are local to this control, like in `_gui_input`.
     #[func]
-    fn focus(&self) {
+    fn send_input_event(&self, event: Gd<InputEvent>) {
+        let size = self.base().get_size();
+        let position = event.clone().try_cast::<InputEventMouse>().map(|mouse| mouse.get_position()).unwrap_or_default();
+        let uv = if size.x > 0.0 && size.y > 0.0 { position / size } else { Vector2::ZERO };
+        self.send_input_at_uv(event, uv);
+    }
+
+    #[func]
+    fn set_background_color(&mut self, color: Color) {
+        self.background_color = color;
         if let Some(webview) = &self.webview {
-            let _ = webview.focus();
+            let _ = webview.set_background_color(self.background_rgba());
         }
     }
 
     #[func]
-    fn focus_parent(&self) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.focus_parent();
+    fn get_zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    #[func]
+    fn set_zoom(&mut self, zoom: f64) {
+        if zoom <= 0.0 {
+            godot_error!("[Godot WRY] Zoom must be greater than 0, got {}.", zoom);
+            return;
         }
+
+        self.zoom = zoom;
+        self.apply_zoom();
     }
 
     #[func]
-    fn print(&self) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.print();
+    fn set_zoom_follows_content_scale(&mut self, follow: bool) {
+        self.zoom_follows_content_scale = follow;
+        self.apply_zoom();
+    }
+
+    #[func]
+    fn post_message(&self, message: Variant) {
+        let data = serde_json::json!({ "detail": variant_to_json(&message) });
+        let script = format!("document.dispatchEvent(new CustomEvent('message', {}))", data);
+        self.send_to_page(script);
+    }
+
+    #[func]
+    fn post_binary(&self, data: PackedByteArray) {
+        let id = self.next_binary_id.get();
+        self.next_binary_id.set(id + 1);
+        self.outgoing_binary.lock().unwrap().insert(id, data.as_slice().to_vec());
+
+        // the page pulls the bytes through the godot-ipc protocol, avoiding any text encoding
+        if self.send_to_page(format!("window.godot._receiveBinary({})", id)) {
+            self.announced_binary_id.store(id + 1, Ordering::SeqCst);
         }
     }
 
     #[func]
-    fn reload(&self) {
-        if let Some(webview) = &self.webview {
-            let _ = webview.reload();
+    fn emit_event(&self, name: GString, data: Variant) {
+        let script = format!(
+            "window.godot._dispatch({}, {})",
+            serde_json::Value::String(String::from(&name)),
+            variant_to_json(&data)
+        );
+        self.send_to_page(script);
+    }
+
+    #[func]
+    fn on_event(&mut self, name: GString, callable: Callable) {
+        self.event_handlers.entry(String::from(&name)).or_default().push(callable);
+    }
+
+    #[func]
+    fn off_event(&mut self, name: GString, callable: Callable) {
+        if let Some(handlers) = self.event_handlers.get_mut(&String::from(&name)) {
+            handlers.retain(|handler| *handler != callable);
         }
     }
-}
 
-lazy_static! {
-    static ref CURRENT_BUTTON_MASK: Mutex<MouseButtonMask> = Mutex::new(MouseButtonMask::default());
-
-    static ref GODOT_KEYS: HashMap<&'static str, Key> = HashMap::from([
-        // https://docs.godotengine.org/en/stable/classes/class_%40globalscope.html#enum-globalscope-key
-
-        ("a", Key::A),
-        ("A", Key::A),
-        ("b", Key::B),
-        ("B", Key::B),
-        ("c", Key::C),
-        ("C", Key::C),
-        ("d", Key::D),
-        ("D", Key::D),
-        ("e", Key::E),
-        ("E", Key::E),
-        ("f", Key::F),
-        ("F", Key::F),
-        ("g", Key::G),
-        ("G", Key::G),
-        ("h", Key::H),
-        ("H", Key::H),
-        ("i", Key::I),
-        ("I", Key::I),
-        ("j", Key::J),
-        ("J", Key::J),
-        ("k", Key::K),
-        ("K", Key::K),
-        ("l", Key::L),
-        ("L", Key::L),
-        ("m", Key::M),
-        ("M", Key::M),
-        ("n", Key::N),
-        ("N", Key::N),
-        ("o", Key::O),
-        ("O", Key::O),
-        ("p", Key::P),
-        ("P", Key::P),
-        ("q", Key::Q),
-        ("Q", Key::Q),
-        ("r", Key::R),
-        ("R", Key::R),
-        ("s", Key::S),
-        ("S", Key::S),
-        ("t", Key::T),
-        ("T", Key::T),
-        ("u", Key::U),
-        ("U", Key::U),
-        ("v", Key::V),
-        ("V", Key::V),
-        ("w", Key::W),
-        ("W", Key::W),
-        ("x", Key::X),
-        ("X", Key::X),
-        ("y", Key::Y),
-        ("Y", Key::Y),
-        ("z", Key::Z),
-        ("Z", Key::Z),
-        
-        ("0", Key::KEY_0),
-        ("1", Key::KEY_1),
-        ("2", Key::KEY_2),
-        ("3", Key::KEY_3),
-        ("4", Key::KEY_4),
-        ("5", Key::KEY_5),
-        ("6", Key::KEY_6),
-        ("7", Key::KEY_7),
-        ("8", Key::KEY_8),
-        ("9", Key::KEY_9),
-        ("Numpad0", Key::KP_0),
-        ("Numpad1", Key::KP_1),
-        ("Numpad2", Key::KP_2),
-        ("Numpad3", Key::KP_3),
-        ("Numpad4", Key::KP_4),
-        ("Numpad5", Key::KP_5),
-        ("Numpad6", Key::KP_6),
-        ("Numpad7", Key::KP_7),
-        ("Numpad8", Key::KP_8),
-        ("Numpad9", Key::KP_9),
-        
-        ("F1", Key::F1),
-        ("F2", Key::F2),
-        ("F3", Key::F3),
-        ("F4", Key::F4),
-        ("F5", Key::F5),
-        ("F6", Key::F6),
-        ("F7", Key::F7),
-        ("F8", Key::F8),
-        ("F9", Key::F9),
-        ("F10", Key::F10),
-        ("F11", Key::F11),
-        ("F12", Key::F12),
-        ("F13", Key::F13),
-        ("F14", Key::F14),
-        ("F15", Key::F15),
-        ("F16", Key::F16),
-        ("F17", Key::F17),
-        ("F18", Key::F18),
-        ("F19", Key::F19),
-        ("F20", Key::F20),
-        ("F21", Key::F21),
-        ("F22", Key::F22),
-        ("F23", Key::F23),
-        ("F24", Key::F24),
-        
-        ("ArrowUp", Key::UP),
-        ("ArrowDown", Key::DOWN),
-        ("ArrowLeft", Key::LEFT),
-        ("ArrowRight", Key::RIGHT),
-        
-        ("Enter", Key::ENTER),
-        ("NumpadEnter", Key::KP_ENTER),
-        ("Tab", Key::TAB),
-        ("Space", Key::SPACE),
-        (" ", Key::SPACE),
-        ("Backspace", Key::BACKSPACE),
-        ("Escape", Key::ESCAPE),
-        ("CapsLock", Key::CAPSLOCK),
-        ("ScrollLock", Key::SCROLLLOCK),
-        ("NumLock", Key::NUMLOCK),
-        ("PrintScreen", Key::PRINT),
-        ("Pause", Key::PAUSE),
-        ("Insert", Key::INSERT),
-        ("Home", Key::HOME),
-        ("PageUp", Key::PAGEUP),
-        ("Delete", Key::DELETE),
-        ("End", Key::END),
-        ("PageDown", Key::PAGEDOWN),
-        
-        ("Shift", Key::SHIFT),
-        ("Control", Key::CTRL),
-        ("Alt", Key::ALT),
-        ("AltGraph", Key::ALT),
-        ("Meta", Key::META),
-        ("ContextMenu", Key::MENU),
-        
-        ("NumpadMultiply", Key::KP_MULTIPLY),
-        ("NumpadDivide", Key::KP_DIVIDE),
-        ("NumpadAdd", Key::KP_ADD),
-        ("NumpadSubtract", Key::KP_SUBTRACT),
-        ("NumpadDecimal", Key::KP_PERIOD),
-        
-        ("MediaPlayPause", Key::MEDIAPLAY),
-        ("MediaStop", Key::MEDIASTOP),
-        ("MediaTrackNext", Key::MEDIANEXT),
-        ("MediaTrackPrevious", Key::MEDIAPREVIOUS),
-        ("VolumeDown", Key::VOLUMEDOWN),
-        ("VolumeUp", Key::VOLUMEUP),
-        ("VolumeMute", Key::VOLUMEMUTE),
-        
-        ("BrowserBack", Key::BACK),
-        ("BrowserForward", Key::FORWARD),
-        ("BrowserRefresh", Key::REFRESH),
-        ("BrowserStop", Key::STOP),
-        ("BrowserSearch", Key::SEARCH),
-        ("BrowserHome", Key::HOMEPAGE),
-        
-        ("`", Key::QUOTELEFT),
-        ("~", Key::ASCIITILDE),
-        ("!", Key::EXCLAM),
-        ("@", Key::AT),
-        ("#", Key::NUMBERSIGN),
-        ("$", Key::DOLLAR),
-        ("%", Key::PERCENT),
-        ("^", Key::ASCIICIRCUM),
-        ("&", Key::AMPERSAND),
-        ("*", Key::ASTERISK),
-        ("(", Key::PARENLEFT),
-        (")", Key::PARENRIGHT),
-        ("-", Key::MINUS),
-        ("_", Key::UNDERSCORE),
-        ("=", Key::EQUAL),
-        ("+", Key::PLUS),
-        ("[", Key::BRACKETLEFT),
-        ("{", Key::BRACELEFT),
-        ("]", Key::BRACKETRIGHT),
-        ("}", Key::BRACERIGHT),
-        ("\\", Key::BACKSLASH),
-        ("|", Key::BAR),
-        (";", Key::SEMICOLON),
-        (":", Key::COLON),
-        ("'", Key::APOSTROPHE),
-        ("\"", Key::QUOTEDBL),
-        (",", Key::COMMA),
-        ("<", Key::LESS),
-        (".", Key::PERIOD),
-        (">", Key::GREATER),
-        ("/", Key::SLASH),
-        ("?", Key::QUESTION),
-    ]);
-}
+    #[func]
+    fn _handle_event(&self, name: GString, data: Variant) {
+        if let Some(handlers) = self.event_handlers.get(&String::from(&name)) {
+            for handler in handlers.iter().filter(|handler| handler.is_valid()) {
+                handler.callv(&varray![data.clone()]);
+            }
+        }
+    }
+
+    #[func]
+    fn register_rpc(&mut self, name: GString, callable: Callable) {
+        self.rpc_handlers.insert(String::from(&name), callable);
+    }
+
+    #[func]
+    fn unregister_rpc(&mut self, name: GString) {
+        self.rpc_handlers.remove(&String::from(&name));
+    }
+
+    /// Returned from an RPC handler to reject the JavaScript promise with `message`.
+    #[func]
+    fn rpc_error(message: GString) -> Dictionary {
+        let mut error = Dictionary::new();
+        error.set(RPC_ERROR_KEY, message);
+        error
+    }
+
+    #[func]
+    fn invoke_js(&self, method: GString, args: VariantArray, callback: Callable) {
+        let id = self.next_js_rpc_id.get();
+        self.next_js_rpc_id.set(id + 1);
+
+        if callback.is_valid() {
+            self.js_rpc_callbacks.borrow_mut().insert(id, callback);
+        }
+
+        let script = format!(
+            "window.godot._call({}, {}, {})",
+            id,
+            serde_json::Value::String(String::from(&method)),
+            variant_to_json(&args.to_variant())
+        );
+        self.send_to_page(script);
+    }
+
+    #[func]
+    fn _handle_rpc_call(&self, id: i64, method: GString, args: VariantArray) {
+        let (result, error) = match self.rpc_handlers.get(&String::from(&method)) {
+            Some(callable) if callable.is_valid() => {
+                let result = callable.callv(&args);
+                match result.try_to::<Dictionary>().ok().and_then(|d| d.get(RPC_ERROR_KEY)) {
+                    Some(error) => (serde_json::Value::Null, serde_json::Value::String(error.stringify().to_string())),
+                    None => (variant_to_json(&result), serde_json::Value::Null),
+                }
+            },
+            _ => (serde_json::Value::Null, serde_json::Value::String(format!("Unknown RPC method \"{}\"", method))),
+        };
+
+        self.resolve_rpc_call(id, result, error);
+    }
+
+    #[func]
+    fn _reject_rpc_call(&self, id: i64, error: GString) {
+        self.resolve_rpc_call(id, serde_json::Value::Null, serde_json::Value::String(String::from(&error)));
+    }
+
+    #[func]
+    fn _handle_rpc_result(&self, id: i64, result: Variant, error: Variant) {
+        // release the borrow before calling, the callback may invoke another function
+        let callback = self.js_rpc_callbacks.borrow_mut().remove(&id);
+        if let Some(callback) = callback {
+            callback.callv(&varray![result, error]);
+        }
+    }
+
+    #[func]
+    fn resize(&self) {
+        if let Some(host) = &self.offscreen_host {
+            host.set_size(self.offscreen_size);
+            return;
+        }
+
+        if let Some(webview) = &self.webview {
+            let rect = if self.full_window_size {
+                let viewport_size = self.base().get_tree().expect("Could not get tree").get_root().expect("Could not get viewport").get_size();
+                Rect {
+                    position: PhysicalPosition::new(0, 0).into(),
+                    size: PhysicalSize::new(viewport_size.x, viewport_size.y).into(),
+                }
+            } else {
+                let window_rect = self.window_rect();
+                Rect {
+                    position: PhysicalPosition::new(window_rect.position.x, window_rect.position.y).into(),
+                    size: PhysicalSize::new(window_rect.size.x, window_rect.size.y).into(),
+                }
+            };
+            let _ = webview.set_bounds(rect);
+
+            // forwarded input is relative to the webview, Godot expects it relative to the window
+            let origin = if self.full_window_size { Vector2::ZERO } else { self.window_rect().position };
+            self.webview_origin.set(origin);
+        }
+    }
+
+    #[func]
+    fn eval(&self, script: GString) {
+        self.send_to_page(String::from(script));
+    }
+
+    #[func]
+    fn is_page_ready(&self) -> bool {
+        self.page_ready.load(Ordering::SeqCst)
+    }
+
+    #[func]
+    fn _handle_bridge_ready(&self) {
+        self.page_ready.store(true, Ordering::SeqCst);
+
+        let queued = std::mem::take(&mut *self.outbound_queue.borrow_mut());
+        for script in queued {
+            self.evaluate_script(&script);
+        }
+        self.announced_binary_id.store(self.next_binary_id.get(), Ordering::SeqCst);
+    }
+
+    #[func]
+    fn update_visibility(&self) {
+        // hiding an offscreen webview would blank its snapshots, the control simply stops drawing it
+        if self.offscreen_host.is_some() {
+            return;
+        }
+
+        if let Some(webview) = &self.webview {
+            let visibility = self.base().is_visible_in_tree();
+            webview.set_visible(visibility).expect("Could not set visibility");
+            self.resize()
+        }
+    }
+
+    #[func]
+    fn set_visible(&self, visibility: bool) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.set_visible(visibility);
+        }
+    }
+
+    #[func]
+    fn load_html(&self, html: GString) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.load_html(&*String::from(html));
+        }
+    }
+
+    #[func]
+    fn load_url(&self, url: GString) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.load_url(&*String::from(url));
+        }
+    }
+
+    /// The page's current URL. The `url` property keeps what was last assigned to it.
+    #[func]
+    fn get_current_url(&self) -> GString {
+        if let Some(webview) = &self.webview {
+            if let Ok(url) = webview.url() {
+                return url.into();
+            }
+        }
+        GString::new()
+    }
+
+    #[func]
+    fn go_back(&self) {
+        if let Some(webview) = &self.webview {
+            navigation::go_back(webview);
+        }
+    }
+
+    #[func]
+    fn go_forward(&self) {
+        if let Some(webview) = &self.webview {
+            navigation::go_forward(webview);
+        }
+    }
+
+    #[func]
+    fn can_go_back(&self) -> bool {
+        if let Some(webview) = &self.webview {
+            return navigation::can_go_back(webview, self.reported_history.get().0);
+        }
+        false
+    }
+
+    #[func]
+    fn can_go_forward(&self) -> bool {
+        if let Some(webview) = &self.webview {
+            return navigation::can_go_forward(webview, self.reported_history.get().1);
+        }
+        false
+    }
+
+    #[func]
+    fn _handle_url_changed(&self, url: GString, can_go_back: Variant, can_go_forward: Variant) {
+        let (mut reported_back, mut reported_forward) = self.reported_history.get();
+        if let Ok(can_go_back) = can_go_back.try_to::<bool>() {
+            reported_back = Some(can_go_back);
+        }
+        if let Ok(can_go_forward) = can_go_forward.try_to::<bool>() {
+            reported_forward = Some(can_go_forward);
+        }
+        self.reported_history.set((reported_back, reported_forward));
+
+        let url = if url.is_empty() { self.get_current_url() } else { url };
+        if url.is_empty() || *self.current_url.borrow() == String::from(&url) {
+            return;
+        }
+
+        self.current_url.replace(String::from(&url));
+        self.base().clone().emit_signal("url_changed", &[url.to_variant()]);
+    }
+
+    /// Captures what the page currently shows, then calls `callback` with the image, or `null` if it failed. Only supported on Linux.
+    #[func]
+    fn capture_screenshot(&self, callback: Callable) {
+        self.capture(None, callback);
+    }
+
+    /// Like `capture_screenshot()`, keeping only the first element matching `selector`, scrolled into view if needed.
+    #[func]
+    fn capture_element_screenshot(&self, selector: GString, callback: Callable) {
+        self.capture(Some(&String::from(&selector)), callback);
+    }
+
+    #[func]
+    fn _handle_screenshot(&self, callback: Callable, image: Variant) {
+        callback.callv(&varray![image]);
+    }
+
+    #[func]
+    fn clear_all_browsing_data(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.clear_all_browsing_data();
+        }
+    }
+
+    #[func]
+    fn get_cookies(&self, url: GString) -> Array<Dictionary> {
+        let mut result = Array::new();
+        if let Some(webview) = &self.webview {
+            let cookies = if url.is_empty() {
+                webview.cookies()
+            } else {
+                webview.cookies_for_url(&String::from(&url))
+            };
+
+            match cookies {
+                Ok(cookies) => cookies.iter().for_each(|cookie| result.push(&cookie_to_dictionary(cookie))),
+                Err(error) => godot_error!("[Godot WRY] Could not get cookies: {}", error),
+            }
+        }
+        result
+    }
+
+    #[func]
+    fn set_cookie(&self, cookie: Dictionary) {
+        if let Some(webview) = &self.webview {
+            if let Err(error) = CookieParams::from_dictionary(&cookie).and_then(|params| cookies::set_cookie(webview, &params)) {
+                godot_error!("[Godot WRY] Could not set the cookie: {}", error);
+            }
+        }
+    }
+
+    #[func]
+    fn delete_cookie(&self, name: GString, url: GString) {
+        if let Some(webview) = &self.webview {
+            cookies::delete_cookie(webview, &String::from(&name), &String::from(&url));
+        }
+    }
+
+    #[func]
+    fn clear_cookies(&self) {
+        if let Some(webview) = &self.webview {
+            cookies::clear_cookies(webview);
+        }
+    }
+
+    #[func]
+    fn close_devtools(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.close_devtools();
+        }
+    }
+
+    #[func]
+    fn open_devtools(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.open_devtools();
+        }
+    }
+
+    #[func]
+    fn is_devtools_open(&self) -> bool {
+        if let Some(webview) = &self.webview {
+            return webview.is_devtools_open();
+        }
+        false
+    }
+
+    #[func]
+    fn focus(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.focus();
+        }
+    }
+
+    #[func]
+    fn focus_parent(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.focus_parent();
+        }
+    }
+
+    #[func]
+    fn print(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.print();
+        }
+    }
+
+    #[func]
+    fn reload(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.reload();
+        }
+    }
+}
+
+impl WebView {
+    /// Hands queued forwarded input to Godot, at most `input_flush_rate` times per second.
+    fn flush_input_events(&mut self, delta: f64) {
+        self.time_since_input_flush += delta;
+        if self.input_flush_rate > 0.0 && self.time_since_input_flush < 1.0 / self.input_flush_rate {
+            return;
+        }
+
+        self.time_since_input_flush = 0.0;
+        self.input_queue.borrow_mut().flush();
+    }
+
+    /// Builds a native webview with this node's settings, its handlers serve whichever node registers under `webview_id`.
+    /// An offscreen webview is built in `offscreen`'s hidden window instead of Godot's.
+    /// The proxy claim is held for as long as the webview lives.
+    fn build_webview(
+        &self,
+        webview_id: &str,
+        url: Option<String>,
+        html: Option<String>,
+        visible: bool,
+        offscreen: Option<&OffscreenHost>,
+    ) -> Option<(wry::WebView, Option<ProxyClaim>)> {
+        let window = GodotWindow;
+
+        // remove WS_CLIPCHILDREN from the window style
+        // otherwise, transparent on windows won't work
+        #[cfg(target_os = "windows")]
+        {
+            let handle = window.window_handle().unwrap().as_raw();
+            let raw_handle: HWND = match handle {
+                RawWindowHandle::Win32(win32) => HWND(win32.hwnd.get() as _),
+                _ => {
+                    panic!("Unsupported window handle type");
+                }
+            };
+
+            unsafe {
+                let current_style = GetWindowLongPtrA(raw_handle, GWL_STYLE);
+                // remove WS_CLIPCHILDREN
+                SetWindowLongPtrA(raw_handle, GWL_STYLE, current_style & !0x02000000);
+            };
+        }
+
+        let proxy_config = if self.proxy_url.is_empty() {
+            None
+        } else {
+            match parse_proxy_url(&String::from(&self.proxy_url)) {
+                Ok(proxy_config) => Some(proxy_config),
+                Err(error) => {
+                    // better no webview than one silently bypassing the proxy
+                    godot_error!("[Godot WRY] Invalid proxy_url, webview will not be created: {}", error);
+                    return None;
+                }
+            }
+        };
+
+        #[cfg(target_os = "macos")]
+        if proxy_config.is_some() {
+            godot_warn!("[Godot WRY] proxy_url is not supported on macOS and will be ignored.");
+        }
+
+        let data_directory = if self.data_directory.is_empty() {
+            String::new()
+        } else {
+            String::from(&ProjectSettings::singleton().globalize_path(&self.data_directory))
+        };
+
+        // webviews with the same data directory share a network session, and with it a single proxy
+        let proxy_claim = if self.incognito || cfg!(target_os = "macos") {
+            None
+        } else {
+            match claim_proxy(&data_directory, &String::from(&self.proxy_url)) {
+                Ok(claim) => Some(claim),
+                Err(error) => {
+                    godot_error!("[Godot WRY] Webview will not be created: {}", error);
+                    return None;
+                }
+            }
+        };
+
+        let ipc_webview_id = webview_id.to_string();
+        let load_webview_id = webview_id.to_string();
+        let navigation_webview_id = webview_id.to_string();
+        let input_token = if self.forward_input_events { generate_token() } else { String::new() };
+        let forward_script = FORWARD_INPUT_SCRIPT.replace("__GODOT_WRY_INPUT_TOKEN__", &input_token);
+
+        // webviews with the same data directory share a context, and with it their storage.
+        // Incognito webviews get an ephemeral context from wry instead, the shared one would never see their protocols
+        let (shared_context, new_web_context) = if data_directory.is_empty() || self.incognito {
+            (None, true)
+        } else {
+            let (context, created) = shared_web_context(PathBuf::from(&data_directory));
+            (Some(context), created)
+        };
+        let mut web_context = shared_context.as_ref().map(|context| context.borrow_mut());
+
+        let webview_builder = WebViewBuilder::with_attributes(WebViewAttributes {
+            context: web_context.as_deref_mut(),
+            url,
+            html,
+            transparent: self.transparent,
+            background_color: Some(self.background_rgba()),
+            devtools: self.devtools,
+            // headers: Some(HeaderMap::try_from(self.headers.iter_shared().typed::<GString, Variant>()).unwrap_or_default()),
+            user_agent: Some(String::from(&self.user_agent)),
+            zoom_hotkeys_enabled: self.zoom_hotkeys,
+            clipboard: self.clipboard,
+            incognito: self.incognito,
+            focused: visible && self.focused_when_created,
+            visible,
+            autoplay: self.autoplay,
+            accept_first_mouse: true,
+            proxy_config,
+            ..Default::default()
+        })
+            .with_ipc_handler(move |req: Request<String>| {
+                // nobody owns a webview waiting in the pool
+                let Some(state) = webview_state(&ipc_webview_id) else { return };
+                let base = state.base.clone();
+                let body = req.body().as_str();
+                let origin = origin_of(req.uri());
+                let json = serde_json::from_str::<serde_json::Value>(body).ok();
+
+                // messages from our own scripts are tagged, anything else is user IPC
+                let kind = json.as_ref().and_then(|json| json.get(BRIDGE_MESSAGE_KEY)).map(|kind| kind.as_str().unwrap_or(""));
+
+                // the bridge keeps track of every page, trusted or not, these only update the node's own bookkeeping
+                match (kind, &json) {
+                    (Some("url_changed"), Some(message)) => {
+                        // the url itself is read from the webview, only the history hints come from the page
+                        let can_go_back = message.get("canGoBack").and_then(|v| v.as_bool()).map(|v| v.to_variant()).unwrap_or_default();
+                        let can_go_forward = message.get("canGoForward").and_then(|v| v.as_bool()).map(|v| v.to_variant()).unwrap_or_default();
+
+                        base.clone().call_deferred("_handle_url_changed", &[GString::new().to_variant(), can_go_back, can_go_forward]);
+                        return;
+                    },
+                    (Some("bridge_ready"), _) => {
+                        base.clone().call_deferred("_handle_bridge_ready", &[]);
+                        return;
+                    },
+                    _ => {},
+                }
+
+                // a refused call still gets an answer, or its promise or `invoke_js` callback would wait forever
+                let settle_refused = || {
+                    let id = json.as_ref().and_then(|json| json.get("id")).and_then(|v| v.as_i64()).unwrap_or(0);
+                    match kind {
+                        Some("rpc_call") => {
+                            base.clone().call_deferred("_reject_rpc_call", &[id.to_variant(), "permission denied".to_variant()]);
+                        },
+                        Some("rpc_result") => {
+                            base.clone().call_deferred("_handle_rpc_result", &[id.to_variant(), Variant::nil(), "permission denied".to_variant()]);
+                        },
+                        _ => {},
+                    }
+                };
+
+                // an empty list trusts every origin
+                if !state.trusts(&origin) {
+                    base.clone().call_deferred("emit_signal", &["untrusted_ipc_message".to_variant(), origin.as_str().to_variant(), body.to_variant()]);
+                    settle_refused();
+                    return;
+                }
+                let capability = match kind {
+                    None => Some("ipc"),
+                    Some("input") => Some("input"),
+                    Some("rpc_call") | Some("rpc_result") => Some("rpc"),
+                    Some("event") => Some("events"),
+                    Some(_) => None,
+                };
+                if let Some(capability) = capability {
+                    if !state.permissions.allows(&origin, capability) {
+                        base.clone().call_deferred("emit_signal", &["permission_denied".to_variant(), origin.as_str().to_variant(), capability.to_variant()]);
+                        settle_refused();
+                        return;
+                    }
+                }
+
+                if let (Some(kind), Some(message)) = (kind, &json) {
+                    match kind {
+                        "input" => {
+                            let token = message.get("token").and_then(|v| v.as_str()).unwrap_or("");
+                            if input_token.is_empty() || token != input_token {
+                                godot_warn!("[Godot WRY] Rejected a forged input event from {}.", origin);
+                                return;
+                            }
+
+                            if let Some(event) = message.get("event") {
+                                for event in forwarded_input_events(event, state.webview_origin.get(), &mut state.pointer.borrow_mut()) {
+                                    state.input_queue.borrow_mut().push(event, state.coalesce_mouse_motion);
+                                }
+                            }
+                        },
+
+                        "rpc_call" => {
+                            let id = message.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                            let method = message.get("method").and_then(|v| v.as_str()).unwrap_or("");
+                            let args = match message.get("args") {
+                                None => VariantArray::new().to_variant(),
+                                Some(args @ serde_json::Value::Array(_)) => json_to_variant(args),
+                                Some(_) => {
+                                    // `_handle_rpc_call` couldn't take them, and the promise would never settle
+                                    let error = format!("Arguments of RPC method \"{}\" must be an array", method);
+                                    base.clone().call_deferred("_reject_rpc_call", &[id.to_variant(), error.to_variant()]);
+                                    return;
+                                },
+                            };
+
+                            // deferred so handlers are free to call back into the webview
+                            base.clone().call_deferred("_handle_rpc_call", &[id.to_variant(), method.to_variant(), args]);
+                        },
+
+                        "rpc_result" => {
+                            let id = message.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                            let result = message.get("result").map(json_to_variant).unwrap_or_default();
+                            let error = message.get("error").map(json_to_variant).unwrap_or_default();
+
+                            base.clone().call_deferred("_handle_rpc_result", &[id.to_variant(), result, error]);
+                        },
+
+                        "event" => {
+                            let name = message.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                            let data = message.get("data").map(json_to_variant).unwrap_or_default();
+
+                            base.clone().call_deferred("_handle_event", &[name.to_variant(), data]);
+                        },
+
+                        _ => {}
+                    }
+                    return;
+                }
+                
+                // if we get here, this is a regular IPC message
+                // signals are deferred, like RPC results and events, so handlers are free to call back into the webview
+                base.clone().call_deferred("emit_signal", &["ipc_message".to_variant(), body.to_variant(), origin.as_str().to_variant()]);
+
+                // non-JSON payloads are still delivered, as a plain string
+                let message = json.as_ref().map(json_to_variant).unwrap_or_else(|| body.to_variant());
+                base.clone().call_deferred("emit_signal", &["message_received".to_variant(), message]);
+            })
+            .with_navigation_handler(move |url| {
+                if let Some(state) = webview_state(&navigation_webview_id) {
+                    state.last_navigation.replace(url.parse::<http::Uri>().ok());
+                }
+                true
+            })
+            .with_on_page_load_handler(move |event, url| {
+                let Some(state) = webview_state(&load_webview_id) else { return };
+                match event {
+                    // the new document has no listeners yet, hold messages until its bridge reports ready
+                    PageLoadEvent::Started => {
+                        state.page_ready.store(false, Ordering::SeqCst);
+                        // the new page reports its own history, if it can
+                        state.reported_history.set((None, None));
+                        // payloads announced to the old page will never be fetched, queued ones go to the new page
+                        let announced = state.announced_binary_id.load(Ordering::SeqCst);
+                        state.outgoing_binary.lock().unwrap().retain(|id, _| *id >= announced);
+                    },
+                    PageLoadEvent::Finished => {
+                        state.base.clone().call_deferred("_handle_url_changed", &[url.to_variant(), Variant::nil(), Variant::nil()]);
+                    },
+                }
+            })
+            .with_id(webview_id)
+            .with_initialization_script(BRIDGE_SCRIPT);
+
+        // on Linux, protocols belong to the context and can only be registered once
+        let webview_builder = if new_web_context || cfg!(not(target_os = "linux")) {
+            webview_builder
+                .with_custom_protocol("res".into(), handle_res_request)
+                .with_custom_protocol("godot-ipc".into(), handle_ipc_request)
+        } else {
+            webview_builder
+        };
+
+        // injected on every page load, before any page script runs
+        let webview_builder = if self.forward_input_events {
+            webview_builder.with_initialization_script(&forward_script)
+        } else {
+            webview_builder
+        };
+
+        let webview = match offscreen {
+            Some(host) => host.build(webview_builder),
+            None => webview_builder.build_as_child(&window),
+        };
+        match webview {
+            Ok(webview) => {
+                // wry only configures a proxy when there is one, the session may still use a previous webview's
+                #[cfg(target_os = "linux")]
+                if self.proxy_url.is_empty() && !self.incognito {
+                    proxy::reset_proxy(&webview);
+                }
+                Some((webview, proxy_claim))
+            },
+            Err(error) => {
+                godot_error!("[Godot WRY] Could not create the webview: {}", error);
+                None
+            }
+        }
+    }
+
+    /// Drops the native webview and disconnects it from the node, leaving queued messages for the next one.
+    fn teardown_webview(&mut self) {
+        unregister_webview_state(&std::mem::take(&mut self.webview_id));
+        self.page_ready.store(false, Ordering::SeqCst);
+
+        let Some(webview) = self.webview.take() else { return };
+        // the webview goes first, it lives inside the offscreen window
+        drop(webview);
+        self.offscreen_host = None;
+        self.proxy_claim = None;
+
+        let resize = Callable::from_object_method(&*self.base(), "resize");
+        let update_visibility = Callable::from_object_method(&*self.base(), "update_visibility");
+        if let Some(mut viewport) = self.base().get_tree().and_then(|tree| tree.get_root()) {
+            if viewport.is_connected("size_changed", &resize) {
+                viewport.disconnect("size_changed", &resize);
+            }
+        }
+        if self.base().is_connected("resized", &resize) {
+            self.base_mut().disconnect("resized", &resize);
+        }
+        if self.base().is_connected("visibility_changed", &update_visibility) {
+            self.base_mut().disconnect("visibility_changed", &update_visibility);
+        }
+    }
+
+    /// The settings a webview is built with and can't change afterwards, pooled webviews are only adopted when they match.
+    fn creation_key(&self) -> String {
+        format!(
+            "{:?}",
+            (
+                self.transparent,
+                self.devtools,
+                String::from(&self.user_agent),
+                String::from(&self.data_directory),
+                String::from(&self.proxy_url),
+                self.zoom_hotkeys,
+                self.clipboard,
+                self.incognito,
+                self.autoplay,
+                self.forward_input_events,
+                self.offscreen,
+            )
+        )
+    }
+
+    fn webview_state(&self) -> WebViewState {
+        WebViewState {
+            base: self.base().clone(),
+            outgoing_binary: self.outgoing_binary.clone(),
+            announced_binary_id: self.announced_binary_id.clone(),
+            permissions: OriginPermissions::from_dictionary(&self.origin_permissions),
+            trusted_origins: self.trusted_origins.as_slice().iter().map(String::from).collect(),
+            page_ready: self.page_ready.clone(),
+            input_queue: self.input_queue.clone(),
+            pointer: Rc::new(RefCell::new(PointerState::default())),
+            coalesce_mouse_motion: self.coalesce_mouse_motion,
+            webview_origin: self.webview_origin.clone(),
+            last_navigation: Rc::new(RefCell::new(None)),
+            reported_history: self.reported_history.clone(),
+        }
+    }
+
+    /// Hands settings that only the webview's handlers read over to them, the page keeps running.
+    /// Held buttons and the pending navigation are kept, so the state is updated rather than replaced.
+    fn update_webview_state(&self) {
+        let Some(mut state) = webview_state(&self.webview_id) else { return };
+        let current = self.webview_state();
+        state.permissions = current.permissions;
+        state.trusted_origins = current.trusted_origins;
+        state.coalesce_mouse_motion = current.coalesce_mouse_motion;
+        register_webview_state(&self.webview_id, state);
+    }
+
+    /// Keeps the current page so the next webview opens it, pages loaded from `html` are simply loaded again.
+    fn remember_url(&mut self) {
+        if !self.html.is_empty() {
+            return;
+        }
+        if let Some(webview) = &self.webview {
+            self.restore_url = webview.url().ok();
+        }
+    }
+
+    /// Recreates the webview at the end of the frame to apply settings that can't change on a live one.
+    /// Several properties changed in a row only cause one rebuild.
+    fn rebuild_webview(&mut self) {
+        if self.webview.is_none() || self.rebuild_pending {
+            return;
+        }
+
+        self.rebuild_pending = true;
+        self.base_mut().call_deferred("_rebuild_webview", &[]);
+    }
+
+    /// `background_color` as wry expects it, only a transparent webview keeps the alpha.
+    fn background_rgba(&self) -> wry::RGBA {
+        let color = self.background_color;
+        let alpha = if self.transparent { color.a8() } else { 255 };
+        (color.r8(), color.g8(), color.b8(), alpha)
+    }
+
+    /// Applies `zoom`, multiplied by the root window's `content_scale_factor` when `zoom_follows_content_scale` is on.
+    fn apply_zoom(&self) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.zoom(self.effective_zoom());
+        }
+    }
+
+    /// From the control's local coordinates to the window's pixels, through the canvas transform and the stretch mode's scaling.
+    fn window_transform(&self) -> Transform2D {
+        let base = self.base();
+        let transform = base.get_global_transform_with_canvas();
+        match base.get_viewport() {
+            Some(viewport) => viewport.get_final_transform() * transform,
+            None => transform,
+        }
+    }
+
+    /// The control's rect in the window's pixels.
+    fn window_rect(&self) -> Rect2 {
+        let transform = self.window_transform();
+        let size = transform.basis_xform(self.base().get_size());
+        Rect2::new(transform * Vector2::ZERO, size.abs())
+    }
+
+    fn effective_zoom(&self) -> f64 {
+        let mut zoom = self.zoom;
+        if self.zoom_follows_content_scale {
+            if let Some(root) = self.base().get_tree().and_then(|tree| tree.get_root()) {
+                zoom *= root.get_content_scale_factor() as f64;
+            }
+        }
+        zoom
+    }
+
+    /// Uploads the latest offscreen frame to `texture`, and asks for the next one at most `offscreen_fps` times per second.
+    fn update_offscreen(&mut self, delta: f64) {
+        let frame = match &self.offscreen_host {
+            Some(host) => host.take_frame(),
+            None => return,
+        };
+
+        if let Some(frame) = frame {
+            if self.texture.get_size() == frame.get_size().cast_float() {
+                self.texture.update(&frame);
+            } else {
+                self.texture.set_image(&frame);
+            }
+            self.base_mut().queue_redraw();
+        }
+
+        self.time_since_frame += delta;
+        if self.offscreen_fps > 0.0 && self.time_since_frame < 1.0 / self.offscreen_fps {
+            return;
+        }
+
+        self.time_since_frame = 0.0;
+        if let (Some(host), Some(webview)) = (&self.offscreen_host, &self.webview) {
+            host.request_frame(webview, self.transparent);
+        }
+    }
+
+    /// Snapshots arrive while GTK events are processed, possibly with this node bound, so the callback is deferred.
+    fn capture(&self, selector: Option<&str>, callback: Callable) {
+        let base = self.base().clone();
+        let deliver = move |result: Result<Gd<Image>, String>| {
+            if !base.is_instance_valid() {
+                return;
+            }
+
+            let image = match result {
+                Ok(image) => image.to_variant(),
+                Err(error) => {
+                    godot_error!("[Godot WRY] Could not capture a screenshot: {}", error);
+                    Variant::nil()
+                }
+            };
+            base.clone().call_deferred("_handle_screenshot", &[callback.to_variant(), image]);
+        };
+
+        match &self.webview {
+            Some(webview) => snapshot::capture(webview, selector, self.transparent, deliver),
+            None => deliver(Err("the webview isn't created".into())),
+        }
+    }
+
+    /// Replays `event` in the page, `position` being in CSS pixels, or in device pixels with `device_pixels`.
+    /// Dropped while the page is loading.
+    fn dispatch_input_event(&self, event: &Gd<InputEvent>, position: Vector2, device_pixels: bool) {
+        if !self.page_ready.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(mut dom_event) = dom_input_event(event, position) {
+            if device_pixels {
+                dom_event["devicePixels"] = serde_json::json!(true);
+            }
+            self.evaluate_script(&format!("window.godot._input({})", dom_event));
+        }
+    }
+
+    /// Settles the promise of the JavaScript `invoke` call `id`.
+    fn resolve_rpc_call(&self, id: i64, result: serde_json::Value, error: serde_json::Value) {
+        let script = format!("window.godot._resolve({}, {}, {})", id, result, error);
+        self.evaluate_script(&script);
+    }
+
+    fn evaluate_script(&self, script: &str) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.evaluate_script(script);
+        }
+    }
+
+    /// Evaluates `script` right away if the page is ready, otherwise queues it until the bridge reports ready.
+    /// Returns whether it was evaluated right away.
+    fn send_to_page(&self, script: String) -> bool {
+        if !self.queue_until_ready || (self.webview.is_some() && self.page_ready.load(Ordering::SeqCst)) {
+            self.evaluate_script(&script);
+            return true;
+        }
+
+        let mut queue = self.outbound_queue.borrow_mut();
+        if self.max_queue_size > 0 && queue.len() >= self.max_queue_size as usize {
+            match self.queue_drop_policy {
+                QueueDropPolicy::DropOldest => {
+                    godot_warn!("[Godot WRY] Outbound queue is full, dropping the oldest message.");
+                    queue.pop_front();
+                },
+                QueueDropPolicy::DropNewest => {
+                    godot_warn!("[Godot WRY] Outbound queue is full, dropping the newest message.");
+                    return false;
+                },
+            }
+        }
+        queue.push_back(script);
+        false
+    }
+}
+
+fn is_headless() -> bool {
+    DisplayServer::singleton().get_name() == "headless".into()
+}
+
+/// Generates a random per-session secret, used to tell forwarded input apart from page scripts.
+fn generate_token() -> String {
+    Crypto::new_gd()
+        .generate_random_bytes(16)
+        .as_slice()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+const BRIDGE_SCRIPT: &str = include_str!("bridge.js");
+
+const FORWARD_INPUT_SCRIPT: &str = include_str!("forward_input.js");
+
+const BRIDGE_MESSAGE_KEY: &str = "__godot_wry";
+
+const RPC_ERROR_KEY: &str = "__rpc_error";
@@ -0,0 +1,47 @@
+//! History navigation. Linux asks WebKitGTK directly, other platforms go through the page's `history`
+//! and rely on the bridge reporting what the Navigation API knows.
+
+#[cfg(target_os = "linux")]
+mod native {
+    use webkit2gtk::prelude::*;
+    use wry::WebViewExtUnix;
+
+    pub fn go_back(webview: &wry::WebView) {
+        webview.webview().go_back();
+    }
+
+    pub fn go_forward(webview: &wry::WebView) {
+        webview.webview().go_forward();
+    }
+
+    pub fn can_go_back(webview: &wry::WebView, _reported: Option<bool>) -> bool {
+        webview.webview().can_go_back()
+    }
+
+    pub fn can_go_forward(webview: &wry::WebView, _reported: Option<bool>) -> bool {
+        webview.webview().can_go_forward()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use native::{can_go_back, can_go_forward, go_back, go_forward};
+
+#[cfg(not(target_os = "linux"))]
+pub fn go_back(webview: &wry::WebView) {
+    let _ = webview.evaluate_script("history.back()");
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn go_forward(webview: &wry::WebView) {
+    let _ = webview.evaluate_script("history.forward()");
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn can_go_back(_webview: &wry::WebView, reported: Option<bool>) -> bool {
+    reported.unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn can_go_forward(_webview: &wry::WebView, reported: Option<bool>) -> bool {
+    reported.unwrap_or(false)
+}
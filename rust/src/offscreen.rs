@@ -0,0 +1,100 @@
+//! Offscreen rendering. The webview lives in a window that is never shown, and its content is read back
+//! with WebKitGTK snapshots, so it can be drawn anywhere a Godot texture can.
+
+#[cfg(not(target_os = "linux"))]
+use {godot::classes::Image, godot::prelude::*};
+
+#[cfg(target_os = "linux")]
+mod native {
+    use crate::snapshot::request_snapshot;
+    use godot::classes::Image;
+    use godot::prelude::*;
+    use gtk::prelude::*;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use wry::{WebViewBuilder, WebViewBuilderExtUnix};
+
+    /// The hidden window an offscreen webview is built in, along with the last frame it rendered.
+    pub struct OffscreenHost {
+        window: gtk::OffscreenWindow,
+        frame: Rc<RefCell<Option<Gd<Image>>>>,
+        capturing: Rc<Cell<bool>>,
+    }
+
+    impl OffscreenHost {
+        pub fn new(size: Vector2i) -> Option<Self> {
+            let window = gtk::OffscreenWindow::new();
+            window.set_default_size(size.x.max(1), size.y.max(1));
+            window.show_all();
+
+            Some(Self {
+                window,
+                frame: Rc::new(RefCell::new(None)),
+                capturing: Rc::new(Cell::new(false)),
+            })
+        }
+
+        pub fn build(&self, builder: WebViewBuilder) -> wry::Result<wry::WebView> {
+            let webview = builder.build_gtk(&self.window)?;
+            self.window.show_all();
+            Ok(webview)
+        }
+
+        pub fn set_size(&self, size: Vector2i) {
+            self.window.resize(size.x.max(1), size.y.max(1));
+        }
+
+        /// Asks WebKitGTK for a snapshot, unless one is still on its way.
+        /// It arrives while GTK events are processed, and is picked up with `take_frame()`.
+        pub fn request_frame(&self, webview: &wry::WebView, transparent: bool) {
+            if self.capturing.replace(true) {
+                return;
+            }
+
+            let frame = self.frame.clone();
+            let capturing = self.capturing.clone();
+            request_snapshot(webview, transparent, move |image| {
+                capturing.set(false);
+                if image.is_some() {
+                    frame.replace(image);
+                }
+            });
+        }
+
+        pub fn take_frame(&self) -> Option<Gd<Image>> {
+            self.frame.borrow_mut().take()
+        }
+    }
+
+    impl Drop for OffscreenHost {
+        fn drop(&mut self) {
+            unsafe { self.window.destroy() };
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use native::OffscreenHost;
+
+/// Offscreen rendering relies on WebKitGTK snapshots, other platforms can't create a host.
+#[cfg(not(target_os = "linux"))]
+pub struct OffscreenHost;
+
+#[cfg(not(target_os = "linux"))]
+impl OffscreenHost {
+    pub fn new(_size: Vector2i) -> Option<Self> {
+        None
+    }
+
+    pub fn build(&self, _builder: wry::WebViewBuilder) -> wry::Result<wry::WebView> {
+        Err(wry::Error::UnsupportedWindowHandle)
+    }
+
+    pub fn set_size(&self, _size: Vector2i) {}
+
+    pub fn request_frame(&self, _webview: &wry::WebView, _transparent: bool) {}
+
+    pub fn take_frame(&self) -> Option<Gd<Image>> {
+        None
+    }
+}
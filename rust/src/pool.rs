@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+
+use crate::web_context::ProxyClaim;
+
+/// A hidden webview built ahead of time, waiting on `about:blank` for a node to adopt it.
+pub struct PooledWebView {
+    pub key: String,
+    pub id: String,
+    pub webview: wry::WebView,
+    pub proxy_claim: Option<ProxyClaim>,
+}
+
+thread_local! {
+    static POOL: RefCell<Vec<PooledWebView>> = RefCell::new(Vec::new());
+}
+
+pub fn add_pooled_webview(pooled: PooledWebView) {
+    POOL.with_borrow_mut(|pool| pool.push(pooled));
+}
+
+/// Takes a pooled webview built with the settings described by `key`, the oldest one first.
+pub fn take_pooled_webview(key: &str) -> Option<PooledWebView> {
+    POOL.with_borrow_mut(|pool| {
+        let index = pool.iter().position(|pooled| pooled.key == key)?;
+        Some(pool.remove(index))
+    })
+}
+
+pub fn pooled_webview_count(key: &str) -> usize {
+    POOL.with_borrow(|pool| pool.iter().filter(|pooled| pooled.key == key).count())
+}
+
+pub fn clear_pool() {
+    // dropped outside the borrow, closing a webview may run callbacks
+    let pool = POOL.with_borrow_mut(std::mem::take);
+    drop(pool);
+}
@@ -1,12 +1,63 @@
-use godot::builtin::GString;
 use godot::classes::file_access::ModeFlags;
 use godot::classes::FileAccess;
+use godot::prelude::*;
 use http::{Request, Response};
-use http::header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use http::header::{ACCEPT_RANGES, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use http::Method;
 use lazy_static::lazy_static;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use wry::WebViewId;
+
+use crate::origin::{is_document_request, request_origin};
+use crate::state::{webview_state, WebViewState};
+
+/// Returns the state of the webview that made `request`, if it's allowed to use custom protocols,
+/// along with the origin of the page that made it, empty when it can't be told.
+fn allowed_protocol_state<T>(webview_id: WebViewId, request: &Request<T>) -> Option<(WebViewState, String)> {
+    let state = webview_state(webview_id)?;
+
+    // the navigation handler saw the page's own URL being loaded, take it so it's only trusted once
+    let navigated = state.last_navigation.borrow().as_ref() == Some(request.uri());
+    if navigated {
+        state.last_navigation.replace(None);
+    }
+
+    let origin = request_origin(request, navigated || is_document_request(request));
+    let allowed = match &origin {
+        Some(origin) => state.permissions.allows(origin, "protocols"),
+        // a request hiding its origin could come from any page
+        None => state.permissions.is_unrestricted(),
+    };
+    let origin = origin.unwrap_or_default();
+    if !allowed {
+        state.base.clone().call_deferred("emit_signal", &["permission_denied".to_variant(), origin.as_str().to_variant(), "protocols".to_variant()]);
+        return None;
+    }
+
+    Some((state, origin))
+}
+
+pub fn handle_res_request(webview_id: WebViewId, request: Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+    match allowed_protocol_state(webview_id, &request) {
+        Some(_) => get_res_response(request),
+        None => get_forbidden_response(),
+    }
+}
+
+pub fn handle_ipc_request(webview_id: WebViewId, request: Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+    match allowed_protocol_state(webview_id, &request) {
+        // binary messages are IPC too, only trusted pages may send or fetch them
+        Some((state, origin)) if !state.trusts(&origin) => get_forbidden_response(),
+        Some((state, _)) => get_ipc_response(request, &state.outgoing_binary, |data| {
+            // requests are handled while the node may be bound, handlers get the signal once it's free
+            state.base.clone().call_deferred("emit_signal", &["binary_message".to_variant(), PackedByteArray::from(data.as_slice()).to_variant()]);
+        }),
+        None => get_forbidden_response(),
+    }
+}
 
 pub fn get_res_response(request: Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
     let root = PathBuf::from("res://");
@@ -108,6 +159,57 @@ pub fn get_res_response(request: Request<Vec<u8>>) -> Response<Cow<'static, [u8]
         });
 }
 
+pub fn get_forbidden_response() -> Response<Cow<'static, [u8]>> {
+    http::Response::builder()
+        .header(CONTENT_TYPE, "text/plain")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .status(403)
+        .body(Cow::from("Forbidden".as_bytes()))
+        .expect("Failed to build 403 response")
+}
+
+/// Serves the `godot-ipc` protocol used by the JavaScript bridge to move raw bytes.
+///
+/// `POST /binary` hands the request body to `on_binary_message`,
+/// `GET /binary/<id>` returns (and forgets) a payload queued by `post_binary`.
+pub fn get_ipc_response(
+    request: Request<Vec<u8>>,
+    outgoing_binary: &Mutex<HashMap<i64, Vec<u8>>>,
+    on_binary_message: impl FnOnce(Vec<u8>),
+) -> Response<Cow<'static, [u8]>> {
+    let path = request.uri().path().trim_start_matches('/');
+
+    let (status, body) = match (request.method(), path) {
+        (&Method::OPTIONS, _) => (204, Vec::new()),
+        (&Method::POST, "binary") => {
+            on_binary_message(request.body().clone());
+            (204, Vec::new())
+        }
+        (&Method::GET, path) if path.starts_with("binary/") => {
+            let payload = path["binary/".len()..]
+                .parse::<i64>()
+                .ok()
+                .and_then(|id| outgoing_binary.lock().unwrap().remove(&id));
+
+            match payload {
+                Some(payload) => (200, payload),
+                None => (404, Vec::new()),
+            }
+        }
+        _ => (404, Vec::new()),
+    };
+
+    // pages are usually served from another origin (https://, res://...), so allow them to fetch us
+    http::Response::builder()
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .header(ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, OPTIONS")
+        .header(ACCESS_CONTROL_ALLOW_HEADERS, "*")
+        .status(status)
+        .body(Cow::from(body))
+        .expect("Failed to build ipc response")
+}
+
 lazy_static! {
     static ref MIME_TYPES: HashMap<&'static str, &'static str> = HashMap::from([
         // https://developer.mozilla.org/en-US/docs/Web/HTTP/Guides/MIME_types/Common_types
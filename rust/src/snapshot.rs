@@ -0,0 +1,154 @@
+//! Page snapshots, taken with WebKitGTK on Linux. Used for offscreen rendering and `capture_screenshot()`.
+
+use godot::classes::image::Format;
+use godot::classes::Image;
+use godot::prelude::*;
+
+/// Converts premultiplied, native endian ARGB32 pixels (cairo's format) into an RGBA8 `Image`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn image_from_argb32(data: &[u8], width: i32, height: i32, stride: i32) -> Option<Gd<Image>> {
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for row in data.chunks(stride as usize).take(height as usize) {
+        for pixel in row[..(width * 4) as usize].chunks_exact(4) {
+            let argb = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            let alpha = (argb >> 24) as u8;
+            let unpremultiply = |channel: u32| match alpha {
+                0 => 0,
+                255 => channel as u8,
+                _ => (channel * 255 / alpha as u32).min(255) as u8,
+            };
+            rgba.extend_from_slice(&[
+                unpremultiply((argb >> 16) & 0xff),
+                unpremultiply((argb >> 8) & 0xff),
+                unpremultiply(argb & 0xff),
+                alpha,
+            ]);
+        }
+    }
+
+    Image::create_from_data(width, height, false, Format::RGBA8, &PackedByteArray::from(rgba.as_slice()))
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use super::image_from_argb32;
+    use godot::classes::Image;
+    use godot::prelude::*;
+    use gtk::cairo::ImageSurface;
+    use gtk::gio::Cancellable;
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use webkit2gtk::prelude::*;
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion};
+    use wry::WebViewExtUnix;
+
+    type ElementRectCallback = Box<dyn FnOnce(Result<String, String>)>;
+
+    thread_local! {
+        /// Element captures waiting for the page to report the element's box. Script callbacks must be `Send`,
+        /// so they only carry the id, they run on the main thread anyway.
+        static PENDING_RECTS: RefCell<HashMap<u64, ElementRectCallback>> = RefCell::new(HashMap::new());
+        static NEXT_RECT_ID: Cell<u64> = const { Cell::new(0) };
+    }
+
+    /// Resolves a pending element capture, or fails it if the script callback is dropped without an answer.
+    struct PendingRect(u64);
+
+    impl PendingRect {
+        fn resolve(&self, result: Result<String, String>) {
+            let callback = PENDING_RECTS.try_with(|pending| pending.borrow_mut().remove(&self.0)).ok().flatten();
+            if let Some(callback) = callback {
+                callback(result);
+            }
+        }
+    }
+
+    impl Drop for PendingRect {
+        fn drop(&mut self) {
+            self.resolve(Err("the page didn't answer".into()));
+        }
+    }
+
+    fn snapshot(page: &webkit2gtk::WebView, transparent: bool, callback: impl FnOnce(Option<Gd<Image>>) + 'static) {
+        let options = if transparent { SnapshotOptions::TRANSPARENT_BACKGROUND } else { SnapshotOptions::NONE };
+        page.snapshot(SnapshotRegion::Visible, options, None::<&Cancellable>, move |result| {
+            let Some(surface) = result.ok().and_then(|surface| ImageSurface::try_from(surface).ok()) else {
+                callback(None);
+                return;
+            };
+
+            surface.flush();
+            let (width, height, stride) = (surface.width(), surface.height(), surface.stride());
+            let mut image = None;
+            let _ = surface.with_data(|data| image = image_from_argb32(data, width, height, stride));
+            callback(image);
+        });
+    }
+
+    /// Snapshots the visible part of the page, `callback` runs while GTK events are processed.
+    pub fn request_snapshot(webview: &wry::WebView, transparent: bool, callback: impl FnOnce(Option<Gd<Image>>) + 'static) {
+        snapshot(&webview.webview(), transparent, callback);
+    }
+
+    /// Crops a snapshot to an element's box, `rect` being `[x, y, width, height, viewport width]` in CSS pixels.
+    fn crop(image: &Gd<Image>, rect: &[f64]) -> Result<Gd<Image>, String> {
+        // snapshots are in device pixels, the page's CSS pixels can be scaled by the zoom and the screen
+        let scale = image.get_width() as f64 / rect[4].max(1.0);
+        let region = Rect2i::new(
+            Vector2i::new((rect[0] * scale).round() as i32, (rect[1] * scale).round() as i32),
+            Vector2i::new((rect[2] * scale).round() as i32, (rect[3] * scale).round() as i32),
+        )
+        .intersect(Rect2i::new(Vector2i::ZERO, image.get_size()))
+        .ok_or_else(|| "the element isn't visible".to_string())?;
+
+        image.get_region(region).ok_or_else(|| "could not crop the snapshot".into())
+    }
+
+    /// Snapshots the page, or the element matching `selector`. `done` runs once, while GTK events are processed.
+    pub fn capture(webview: &wry::WebView, selector: Option<&str>, transparent: bool, done: impl FnOnce(Result<Gd<Image>, String>) + 'static) {
+        let Some(selector) = selector else {
+            request_snapshot(webview, transparent, move |image| done(image.ok_or_else(|| "WebKitGTK couldn't take a snapshot".into())));
+            return;
+        };
+
+        // the element's box, in the page's CSS pixels, along with the viewport width to scale it
+        let script = format!(
+            "(() => {{ const element = document.querySelector({}); if (!element) return null; \
+             element.scrollIntoView({{ block: 'nearest', inline: 'nearest' }}); \
+             const rect = element.getBoundingClientRect(); \
+             return [rect.x, rect.y, rect.width, rect.height, window.innerWidth]; }})()",
+            serde_json::Value::String(selector.to_string())
+        );
+
+        let page = webview.webview();
+        let selector = selector.to_string();
+        let id = NEXT_RECT_ID.with(|next| next.replace(next.get() + 1));
+        let on_rect: ElementRectCallback = Box::new(move |json| {
+            let json = match json {
+                Ok(json) => json,
+                Err(error) => return done(Err(error)),
+            };
+            let rect = match serde_json::from_str::<Vec<f64>>(&json) {
+                Ok(rect) if rect.len() == 5 => rect,
+                _ => return done(Err(format!("no element matches \"{}\"", selector))),
+            };
+
+            snapshot(&page, transparent, move |image| {
+                done(image.ok_or_else(|| "WebKitGTK couldn't take a snapshot".to_string()).and_then(|image| crop(&image, &rect)));
+            });
+        });
+        PENDING_RECTS.with(|pending| pending.borrow_mut().insert(id, on_rect));
+
+        // if the script can't run, dropping the callback fails the capture
+        let pending = PendingRect(id);
+        let _ = webview.evaluate_script_with_callback(&script, move |json| pending.resolve(Ok(json)));
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use native::{capture, request_snapshot};
+
+#[cfg(not(target_os = "linux"))]
+pub fn capture(_webview: &wry::WebView, _selector: Option<&str>, _transparent: bool, done: impl FnOnce(Result<Gd<Image>, String>) + 'static) {
+    done(Err("screenshots are only supported on Linux".into()));
+}
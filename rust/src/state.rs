@@ -0,0 +1,67 @@
+use godot::classes::Control;
+use godot::prelude::*;
+use http::Uri;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicI64};
+use std::sync::{Arc, Mutex};
+
+use crate::input::{InputQueue, PointerState};
+use crate::origin::{origin_matches, OriginPermissions};
+
+/// What the native webview's handlers need to know about the node that owns it.
+/// Handlers look it up by webview id instead of capturing it, because webviews sharing a `WebContext`
+/// share its protocol handlers, and a pooled webview changes owner when a node adopts it.
+#[derive(Clone)]
+pub struct WebViewState {
+    pub base: Gd<Control>,
+    pub outgoing_binary: Arc<Mutex<HashMap<i64, Vec<u8>>>>,
+    /// Payloads below this id were announced to the page, the others are still queued.
+    pub announced_binary_id: Arc<AtomicI64>,
+    pub permissions: OriginPermissions,
+    pub trusted_origins: Vec<String>,
+    pub page_ready: Arc<AtomicBool>,
+    pub input_queue: Rc<RefCell<InputQueue>>,
+    /// Buttons and touches held in this webview, so webviews don't release each other's.
+    pub pointer: Rc<RefCell<PointerState>>,
+    pub coalesce_mouse_motion: bool,
+    /// Where the webview's top left corner is in the window, in pixels.
+    pub webview_origin: Rc<Cell<Vector2>>,
+    /// The last top-level navigation, custom protocol requests for it carry no `Origin` nor `Referer`.
+    pub last_navigation: Rc<RefCell<Option<Uri>>>,
+    /// `canGoBack` and `canGoForward` as the page's bridge last reported them.
+    pub reported_history: Rc<Cell<(Option<bool>, Option<bool>)>>,
+}
+
+impl WebViewState {
+    /// Whether `origin` may use IPC, an empty `trusted_origins` trusts every origin.
+    pub fn trusts(&self, origin: &str) -> bool {
+        self.trusted_origins.is_empty() || self.trusted_origins.iter().any(|pattern| origin_matches(pattern, origin))
+    }
+}
+
+thread_local! {
+    static WEBVIEW_STATES: RefCell<HashMap<String, WebViewState>> = RefCell::new(HashMap::new());
+    static NEXT_WEBVIEW_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+pub fn next_webview_id() -> String {
+    let id = NEXT_WEBVIEW_ID.get();
+    NEXT_WEBVIEW_ID.set(id + 1);
+    format!("godot-wry-{}", id)
+}
+
+pub fn register_webview_state(webview_id: &str, state: WebViewState) {
+    WEBVIEW_STATES.with_borrow_mut(|states| states.insert(webview_id.to_string(), state));
+}
+
+pub fn unregister_webview_state(webview_id: &str) {
+    WEBVIEW_STATES.with_borrow_mut(|states| states.remove(webview_id));
+}
+
+/// Returns the state of the node owning `webview_id`, `None` while it sits unowned in the pool.
+pub fn webview_state(webview_id: &str) -> Option<WebViewState> {
+    // cloned so no borrow is held while signals run user code
+    WEBVIEW_STATES.with_borrow(|states| states.get(webview_id).cloned())
+}
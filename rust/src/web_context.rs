@@ -0,0 +1,65 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use wry::WebContext;
+
+thread_local! {
+    static WEB_CONTEXTS: RefCell<HashMap<PathBuf, Rc<RefCell<WebContext>>>> = RefCell::new(HashMap::new());
+    /// The proxy of each data directory's network session, with the number of live webviews using it.
+    static PROXY_CLAIMS: RefCell<HashMap<String, (String, usize)>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the `WebContext` shared by every webview storing its data in `directory`,
+/// and whether it was just created. Contexts live as long as the game, so later webviews can reuse them.
+pub fn shared_web_context(directory: PathBuf) -> (Rc<RefCell<WebContext>>, bool) {
+    WEB_CONTEXTS.with_borrow_mut(|contexts| {
+        if let Some(context) = contexts.get(&directory) {
+            return (context.clone(), false);
+        }
+
+        let context = Rc::new(RefCell::new(WebContext::new(Some(directory.clone()))));
+        contexts.insert(directory, context.clone());
+        (context, true)
+    })
+}
+
+/// A live webview's use of its network session's proxy, released when dropped.
+pub struct ProxyClaim {
+    data_directory: String,
+}
+
+/// Claims `proxy_url` for the network session of `data_directory`, empty for the platform default one.
+/// Webviews sharing a session share its proxy, so another proxy can only be used once they're all gone.
+pub fn claim_proxy(data_directory: &str, proxy_url: &str) -> Result<ProxyClaim, String> {
+    PROXY_CLAIMS.with_borrow_mut(|claims| {
+        let (claimed_proxy, count) = claims.entry(data_directory.to_string()).or_insert_with(|| (proxy_url.to_string(), 0));
+        if *count > 0 && claimed_proxy != proxy_url {
+            let describe = |proxy: &str| if proxy.is_empty() { "no proxy".to_string() } else { format!("proxy \"{}\"", proxy) };
+            return Err(format!(
+                "other webviews with the same data_directory use {}, they can't be given {}",
+                describe(claimed_proxy),
+                describe(proxy_url)
+            ));
+        }
+
+        *claimed_proxy = proxy_url.to_string();
+        *count += 1;
+        Ok(ProxyClaim { data_directory: data_directory.to_string() })
+    })
+}
+
+impl Drop for ProxyClaim {
+    fn drop(&mut self) {
+        // the claims may already be gone when the game exits
+        let _ = PROXY_CLAIMS.try_with(|claims| {
+            let mut claims = claims.borrow_mut();
+            if let Some((_, count)) = claims.get_mut(&self.data_directory) {
+                *count -= 1;
+                if *count == 0 {
+                    claims.remove(&self.data_directory);
+                }
+            }
+        });
+    }
+}